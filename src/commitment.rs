@@ -0,0 +1,15 @@
+use cosmwasm_std::{Addr, HexBinary, Uint128};
+use sha2::{Digest, Sha256};
+
+/// Commitment for a hidden reserve price: `sha256("{amount}:{salt}")`.
+pub fn reserve_commitment(amount: Uint128, salt: &str) -> HexBinary {
+    let hash = Sha256::digest(format!("{amount}:{salt}").as_bytes());
+    HexBinary::from(hash.as_slice())
+}
+
+/// Commitment for a sealed bid: `sha256("{bidder}:{amount}:{salt}")`. Binding the bidder
+/// keeps others from copying a commitment they've seen on chain.
+pub fn bid_commitment(bidder: &Addr, amount: Uint128, salt: &str) -> HexBinary {
+    let hash = Sha256::digest(format!("{bidder}:{amount}:{salt}").as_bytes());
+    HexBinary::from(hash.as_slice())
+}
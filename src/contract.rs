@@ -1,243 +1,2498 @@
-use cosmwasm_std::{Decimal, DepsMut, MessageInfo, Response, StdResult};
-use cw2::set_contract_version;
+use cosmwasm_std::{
+    Addr, Api, BlockInfo, Coin, Decimal, DepsMut, Env, Order, Response, StdError, StdResult,
+    Storage, Uint128,
+};
+use cw2::{get_contract_version, set_contract_version};
+use cw_utils::{Duration, Scheduled};
+use semver::Version;
 
-use crate::state::{BaseInfo, BASE_INFO};
+use crate::error::ContractError;
+use crate::msg::{
+    AssetKind, AuctionMode, CommissionMode, FeeRecipient, InstantiateMsg, MigrateMsg, MinIncrement,
+    Outcome, Settlement, StrayFunds,
+};
+use crate::state::{
+    bids, v1, BaseInfo, Config, FeeShare, AUCTIONS, CONFIG, DEPOSITS, HELD, HIGHEST_BID,
+    NEXT_AUCTION_ID,
+};
 
 const CONTRACT_NAME: &str = env!("CARGO_PKG_NAME");
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
-pub fn instantiate(
-    deps: DepsMut,
-    info: MessageInfo,
-    owner: Option<String>,
-    commodity: String,
-    bidding_denom: String,
-    commission: Decimal,
-) -> StdResult<Response> {
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+/// How long after the end only the owner can close a hidden-reserve auction without revealing
+/// it, about a day either way. Past it anybody can close it without a sale.
+const RESERVE_REVEAL_GRACE_BLOCKS: u64 = 14_400;
+const RESERVE_REVEAL_GRACE_SECONDS: u64 = 86_400;
+
+pub fn instantiate(deps: DepsMut, msg: InstantiateMsg) -> Result<Response, ContractError> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    NEXT_AUCTION_ID.save(deps.storage, &1)?;
+
+    let fee_recipients = fee_shares(deps.api, msg.fee_recipients.unwrap_or_default())?;
+    CONFIG.save(deps.storage, &Config { fee_recipients })?;
+
+    Ok(Response::new())
+}
+
+fn fee_shares(
+    api: &dyn Api,
+    recipients: Vec<FeeRecipient>,
+) -> Result<Vec<FeeShare>, ContractError> {
+    recipients
+        .into_iter()
+        .map(|recipient| {
+            if recipient.weight == 0 {
+                return Err(ContractError::InvalidFeeRecipients {});
+            }
+            Ok(FeeShare {
+                address: api.addr_validate(&recipient.address)?,
+                weight: recipient.weight,
+            })
+        })
+        .collect()
+}
 
-    let owner_addr = match owner {
-        Some(owner) => deps.api.addr_validate(&owner)?,
-        None => info.sender,
+pub fn migrate(mut deps: DepsMut, env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    let stored = get_contract_version(deps.storage)?;
+    if stored.contract != CONTRACT_NAME {
+        return Err(ContractError::WrongContract {
+            contract: stored.contract,
+        });
+    }
+
+    let parse = |version: &str| {
+        Version::parse(version).map_err(|err| StdError::generic_err(err.to_string()))
+    };
+    let stored_version = parse(&stored.version)?;
+    let current_version = parse(CONTRACT_VERSION)?;
+    if stored_version > current_version {
+        return Err(ContractError::Downgrade {
+            stored: stored.version,
+            current: CONTRACT_VERSION.to_string(),
+        });
+    }
+
+    if stored_version.major < 2 {
+        migrate_v1(deps.branch(), &env.contract.address)?;
+        count_held(deps.storage)?;
+    }
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    Ok(Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute("from_version", stored.version)
+        .add_attribute("to_version", CONTRACT_VERSION))
+}
+
+/// Moves the single auction of a 1.x contract to auction id 1. Features added since get
+/// their defaults. 1.x didn't clear bids on retract, so a closed auction migrated with
+/// leftover bids may still pay them out. Those of losers who already retracted are
+/// stale, so the migration is refused unless the contract still holds every one of them.
+fn migrate_v1(deps: DepsMut, contract: &Addr) -> Result<(), ContractError> {
+    let storage = deps.storage;
+    let auction_id = 1;
+    let legacy = v1::BASE_INFO.load(storage)?;
+    let highest_bid = v1::HIGHEST_BID.may_load(storage)?;
+
+    // A closed auction already paid its winner out
+    let paid = highest_bid
+        .as_ref()
+        .filter(|_| !legacy.active)
+        .map(|bid| bid.address.clone());
+    let legacy_bids = v1::BIDS
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    let owed: Uint128 = legacy_bids
+        .iter()
+        .filter(|(bidder, _)| Some(bidder) != paid.as_ref())
+        .map(|(_, bid)| bid.amount)
+        .sum();
+    let balance = deps
+        .querier
+        .query_balance(contract, &legacy.bidding_denom)?
+        .amount;
+    if owed > balance {
+        return Err(ContractError::UnfundedLegacyBids {
+            owed: Coin::new(owed.u128(), &legacy.bidding_denom).to_string(),
+            balance: Coin::new(balance.u128(), &legacy.bidding_denom).to_string(),
+        });
+    }
+
+    // 1.x had no reserve, so any closed auction with bids was sold
+    let outcome = match (legacy.active, &highest_bid) {
+        (true, _) => Outcome::Pending,
+        (false, Some(_)) => Outcome::Sold,
+        (false, None) => Outcome::NoBids,
     };
     let base_info = BaseInfo {
-        owner: owner_addr,
-        commodity,
-        commission,
-        bidding_denom,
-        active: true,
+        owner: legacy.owner,
+        commodity: legacy.commodity,
+        lot: None,
+        bidding_denom: legacy.bidding_denom,
+        bidding_asset: AssetKind::Native,
+        commission: legacy.commission,
+        start: None,
+        end: None,
+        anti_sniping: None,
+        effective_end: None,
+        reserve: None,
+        starting_price: Uint128::zero(),
+        min_increment: None,
+        mode: AuctionMode::English,
+        settlement: Settlement::FirstPrice,
+        buy_now_price: None,
+        refund_on_outbid: false,
+        withdrawal_penalty: Decimal::zero(),
+        fee_recipients: vec![],
+        commission_mode: CommissionMode::PerBid,
+        accepted_denoms: vec![],
+        stray_funds: StrayFunds::Reject,
+        renounced: false,
+        active: legacy.active,
+        outcome,
     };
-    BASE_INFO.save(deps.storage, &base_info)?;
-    Ok(Response::new())
+    AUCTIONS.save(storage, auction_id, &base_info)?;
+    NEXT_AUCTION_ID.save(storage, &(auction_id + 1))?;
+    v1::BASE_INFO.remove(storage);
+
+    if let Some(highest_bid) = highest_bid {
+        HIGHEST_BID.save(storage, auction_id, &highest_bid)?;
+        v1::HIGHEST_BID.remove(storage);
+    }
+
+    for (bidder, bid) in legacy_bids {
+        bids().save(storage, (auction_id, bidder.clone()), &bid)?;
+        v1::BIDS.remove(storage, bidder);
+    }
+
+    Ok(())
+}
+
+/// Coins held for the deposit `bid` of `bidder`, its breakdown by denom in auctions
+/// accepting several denoms.
+fn held_coins(
+    storage: &dyn Storage,
+    auction_id: u64,
+    base_info: &BaseInfo,
+    bidder: &Addr,
+    bid: Coin,
+) -> StdResult<Vec<Coin>> {
+    if base_info.accepted_denoms.is_empty() {
+        return Ok(vec![bid]);
+    }
+
+    DEPOSITS
+        .prefix((auction_id, bidder.clone()))
+        .range(storage, None, None, Order::Ascending)
+        .map(|deposit| deposit.map(|(denom, amount)| Coin { denom, amount }))
+        .collect()
+}
+
+/// Totals the native funds held for bidders of all auctions in `HELD`, which 1.x didn't track.
+fn count_held(storage: &mut dyn Storage) -> StdResult<()> {
+    for coin in owed_to_bidders(storage)? {
+        HELD.save(storage, &coin.denom, &coin.amount)?;
+    }
+
+    Ok(())
 }
 
-pub mod exec {
-    use cosmwasm_std::{BankMsg, Coin, DepsMut, MessageInfo, Response, StdError, Uint128};
+/// Native funds the deposits of all auctions add up to. The deposit of the winner of a sold
+/// auction has been paid out already.
+fn owed_to_bidders(storage: &dyn Storage) -> StdResult<Vec<Coin>> {
+    let mut owed: Vec<Coin> = vec![];
+    for auction in AUCTIONS.range(storage, None, None, Order::Ascending) {
+        let (auction_id, base_info) = auction?;
+        if base_info.bidding_asset != AssetKind::Native {
+            continue;
+        }
+
+        let winner = match base_info.outcome {
+            Outcome::Sold => HIGHEST_BID
+                .may_load(storage, auction_id)?
+                .map(|winner| winner.address),
+            _ => None,
+        };
+        for deposit in bids()
+            .prefix(auction_id)
+            .range(storage, None, None, Order::Ascending)
+        {
+            let (bidder, bid) = deposit?;
+            if Some(&bidder) == winner.as_ref() {
+                continue;
+            }
+            for coin in held_coins(storage, auction_id, &base_info, &bidder, bid)? {
+                match owed.iter_mut().find(|owed| owed.denom == coin.denom) {
+                    Some(owed) => owed.amount += coin.amount,
+                    None => owed.push(coin),
+                }
+            }
+        }
+    }
+
+    Ok(owed)
+}
+
+/// Adds `coin` to the native funds held for bidders, CW20 deposits aren't tracked.
+fn hold(storage: &mut dyn Storage, base_info: &BaseInfo, coin: &Coin) -> StdResult<()> {
+    if base_info.bidding_asset != AssetKind::Native || coin.amount.is_zero() {
+        return Ok(());
+    }
+
+    HELD.update(storage, &coin.denom, |held| -> StdResult<_> {
+        Ok(held.unwrap_or_default().checked_add(coin.amount)?)
+    })?;
+    Ok(())
+}
+
+/// Removes `coin` paid out of a deposit from the native funds held for bidders.
+fn release(storage: &mut dyn Storage, base_info: &BaseInfo, coin: &Coin) -> StdResult<()> {
+    if base_info.bidding_asset != AssetKind::Native || coin.amount.is_zero() {
+        return Ok(());
+    }
+
+    HELD.update(storage, &coin.denom, |held| -> StdResult<_> {
+        Ok(held.unwrap_or_default().checked_sub(coin.amount)?)
+    })?;
+    Ok(())
+}
+
+/// Smallest total deposit (after commission) a bidder needs to take the lead.
+fn min_next_bid(
+    storage: &dyn Storage,
+    auction_id: u64,
+    base_info: &BaseInfo,
+) -> StdResult<Uint128> {
+    let Some(leader) = HIGHEST_BID.may_load(storage, auction_id)? else {
+        return Ok(base_info.starting_price.max(Uint128::one()));
+    };
+
+    let leading = bids().load(storage, (auction_id, leader.address))?.amount;
+    let increment = match base_info.min_increment {
+        Some(MinIncrement::Amount(amount)) => amount,
+        Some(MinIncrement::Percent(percent)) => leading * percent,
+        None => Uint128::zero(),
+    };
+
+    // The lead always has to be taken by at least one unit
+    Ok(leading.checked_add(increment.max(Uint128::one()))?)
+}
+
+/// Current Dutch auction price, `None` for other modes.
+fn dutch_price(base_info: &BaseInfo, block: &BlockInfo) -> Option<Uint128> {
+    let AuctionMode::Dutch {
+        start_price,
+        floor_price,
+        decrement,
+        step,
+    } = base_info.mode
+    else {
+        return None;
+    };
+
+    // Creation ensures the start is set and in the unit of the step
+    let steps = match (base_info.start, step) {
+        (Some(Scheduled::AtHeight(start)), Duration::Height(step)) => {
+            block.height.saturating_sub(start) / step
+        }
+        (Some(Scheduled::AtTime(start)), Duration::Time(step)) => {
+            block.time.seconds().saturating_sub(start.seconds()) / step
+        }
+        _ => 0,
+    };
+    let drop = decrement.saturating_mul(Uint128::from(steps));
+
+    Some(start_price.saturating_sub(drop).max(floor_price))
+}
+
+pub mod exec {
+    use cosmwasm_std::{
+        from_binary, to_binary, Addr, Api, BankMsg, BlockInfo, Coin, CosmosMsg, Decimal, Deps,
+        DepsMut, Env, HexBinary, MessageInfo, Order, Response, StdError, StdResult, Storage,
+        Uint128, Uint256, WasmMsg,
+    };
+    use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
+    use cw721::{Cw721ExecuteMsg, Cw721ReceiveMsg};
+    use cw_utils::{Duration, Scheduled};
+
+    use crate::commitment::{bid_commitment, reserve_commitment};
+    use crate::error::ContractError;
+    use crate::msg::{
+        AcceptedDenom, AssetKind, AuctionMode, BidHistoryEntry, CommissionMode, CreateAuctionMsg,
+        Cw2981QueryMsg, ExchangeRateResponse, Lot, OracleQueryMsg, Outcome, Rate, ReceiveMsg,
+        ReceiveNftMsg, Reserve, ReserveReveal, RoyaltiesInfoResponse, RoyaltyQueryMsg, Settlement,
+        StrayFunds,
+    };
+    use crate::state::{
+        bids, BaseInfo, Bid, DenomRate, FeeShare, PendingOwner, RateSource, Royalty, Sale,
+        SealedBid, UnitBid, ALLOCATIONS, AUCTIONS, BID_BOOK, BID_HISTORY, BID_HISTORY_LEN, BID_SEQ,
+        CLEARING_PRICE, CONFIG, DEPOSITS, HELD, HIGHEST_BID, NEXT_AUCTION_ID, PENDING_OWNER,
+        RUNNER_UP, SALES, SEALED_BIDS,
+    };
+
+    pub fn create_auction(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        msg: CreateAuctionMsg,
+    ) -> Result<Response, ContractError> {
+        let stray = stray_funds(&msg.stray_funds.clone().unwrap_or_default(), &info, None)?;
+        let resp = create(deps, env, info.sender.clone(), msg, None)?;
+        Ok(refund_stray(resp, &info.sender, stray))
+    }
+
+    pub fn receive_nft(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        msg: Cw721ReceiveMsg,
+    ) -> Result<Response, ContractError> {
+        let sender = deps.api.addr_validate(&msg.sender)?;
+        let lot = Lot::Cw721 {
+            contract: info.sender,
+            token_id: msg.token_id,
+        };
+
+        match from_binary(&msg.msg)? {
+            ReceiveNftMsg::CreateAuction(auction) => create(deps, env, sender, auction, Some(lot)),
+        }
+    }
+
+    fn create(
+        deps: DepsMut,
+        env: Env,
+        sender: Addr,
+        msg: CreateAuctionMsg,
+        lot: Option<Lot>,
+    ) -> Result<Response, ContractError> {
+        if let (Some(start), Some(end)) = (msg.start, msg.end) {
+            if start.partial_cmp(&end) != Some(std::cmp::Ordering::Less) {
+                return Err(ContractError::InvalidSchedule {});
+            }
+        }
+        if let Some(end) = msg.end {
+            if end.is_triggered(&env.block) {
+                return Err(ContractError::InvalidSchedule {});
+            }
+        }
+        if let Some(anti_sniping) = &msg.anti_sniping {
+            let end = msg.end.ok_or(ContractError::InvalidAntiSniping {})?;
+            // Adding a duration of a different unit fails
+            (end + anti_sniping.window).map_err(|_| ContractError::InvalidAntiSniping {})?;
+            (end + anti_sniping.extension).map_err(|_| ContractError::InvalidAntiSniping {})?;
+        }
+
+        let mode = msg.mode.unwrap_or_default();
+        if let AuctionMode::Sealed {
+            reveal_end,
+            unrevealed_penalty,
+        } = &mode
+        {
+            let end = msg.end.ok_or(ContractError::InvalidSchedule {})?;
+            if end.partial_cmp(reveal_end) != Some(std::cmp::Ordering::Less) {
+                return Err(ContractError::InvalidSchedule {});
+            }
+            // Extending the commit phase would let late bidders react to the reveals
+            if msg.anti_sniping.is_some() {
+                return Err(ContractError::InvalidAntiSniping {});
+            }
+            if *unrevealed_penalty > Decimal::one() {
+                return Err(ContractError::InvalidPenalty {});
+            }
+        }
+
+        let mut start = msg.start;
+        if let AuctionMode::Dutch {
+            start_price,
+            floor_price,
+            step,
+            ..
+        } = &mode
+        {
+            if start_price < floor_price {
+                return Err(ContractError::InvalidDutchPrice {});
+            }
+            // The floor acts as the reserve and there is no bidding war to settle
+            if msg.reserve.is_some()
+                || msg.settlement == Some(Settlement::SecondPrice)
+                || msg.anti_sniping.is_some()
+            {
+                return Err(ContractError::WrongAuctionMode {});
+            }
+
+            // The price decays from the start, in the unit of the step
+            let from = match step {
+                Duration::Height(0) | Duration::Time(0) => {
+                    return Err(ContractError::InvalidSchedule {})
+                }
+                Duration::Height(_) => start.unwrap_or(Scheduled::AtHeight(env.block.height)),
+                Duration::Time(_) => start.unwrap_or(Scheduled::AtTime(env.block.time)),
+            };
+            if (from + *step).is_err() {
+                return Err(ContractError::InvalidSchedule {});
+            }
+            start = Some(from);
+        }
+
+        if msg.commission > Decimal::one() {
+            return Err(ContractError::InvalidCommission {});
+        }
+
+        let withdrawal_penalty = msg.withdrawal_penalty.unwrap_or_default();
+        if withdrawal_penalty > Decimal::one() {
+            return Err(ContractError::InvalidPenalty {});
+        }
+        // Only English auctions let bidders withdraw before the close
+        if !withdrawal_penalty.is_zero() && mode != AuctionMode::English {
+            return Err(ContractError::WrongAuctionMode {});
+        }
+
+        let refund_on_outbid = msg.refund_on_outbid.unwrap_or_default();
+        if refund_on_outbid && mode != AuctionMode::English {
+            return Err(ContractError::WrongAuctionMode {});
+        }
+
+        if let Some(buy_now) = msg.buy_now_price {
+            if mode != AuctionMode::English || msg.settlement == Some(Settlement::SecondPrice) {
+                return Err(ContractError::WrongAuctionMode {});
+            }
+            // A buy-now sale has to go through at close, so it can't be under the reserve
+            let below_reserve = match &msg.reserve {
+                Some(Reserve::Public { amount }) => buy_now < *amount,
+                Some(Reserve::Hidden { .. }) => true,
+                None => false,
+            };
+            if below_reserve || buy_now < msg.starting_price.unwrap_or_default() {
+                return Err(ContractError::InvalidBuyNowPrice {});
+            }
+        }
+
+        if let AuctionMode::MultiUnit { quantity } = mode {
+            if quantity == 0 {
+                return Err(ContractError::InvalidQuantity {});
+            }
+            // Units are delivered off chain and priced per unit, not against a single reserve.
+            // Unit bids don't extend the end either.
+            if lot.is_some()
+                || msg.reserve.is_some()
+                || msg.settlement == Some(Settlement::SecondPrice)
+                || msg.anti_sniping.is_some()
+            {
+                return Err(ContractError::WrongAuctionMode {});
+            }
+        }
+
+        let bidding_asset = msg.bidding_asset.unwrap_or_default();
+        if bidding_asset == AssetKind::Cw20 {
+            deps.api.addr_validate(&msg.bidding_denom)?;
+        }
+
+        let accepted_denoms = msg.accepted_denoms.unwrap_or_default();
+        // Deposits in several denoms are only ever paid out whole
+        if !accepted_denoms.is_empty()
+            && (mode != AuctionMode::English
+                || bidding_asset != AssetKind::Native
+                || msg.settlement == Some(Settlement::SecondPrice)
+                || msg.buy_now_price.is_some())
+        {
+            return Err(ContractError::WrongAuctionMode {});
+        }
+        let accepted_denoms = denom_rates(deps.api, &msg.bidding_denom, accepted_denoms)?;
+
+        let fee_recipients = match msg.fee_recipients {
+            Some(recipients) => super::fee_shares(deps.api, recipients)?,
+            // Contracts migrated from 1.x have no config
+            None => {
+                CONFIG
+                    .may_load(deps.storage)?
+                    .unwrap_or_default()
+                    .fee_recipients
+            }
+        };
+
+        let owner_addr = match msg.owner {
+            Some(owner) => deps.api.addr_validate(&owner)?,
+            None => sender,
+        };
+        let base_info = BaseInfo {
+            owner: owner_addr,
+            commodity: msg.commodity,
+            lot,
+            commission: msg.commission,
+            bidding_denom: msg.bidding_denom,
+            bidding_asset,
+            start,
+            end: msg.end,
+            anti_sniping: msg.anti_sniping,
+            effective_end: msg.end,
+            reserve: msg.reserve,
+            starting_price: msg.starting_price.unwrap_or_default(),
+            min_increment: msg.min_increment,
+            mode,
+            settlement: msg.settlement.unwrap_or_default(),
+            buy_now_price: msg.buy_now_price,
+            refund_on_outbid,
+            withdrawal_penalty,
+            fee_recipients,
+            commission_mode: msg.commission_mode.unwrap_or_default(),
+            accepted_denoms,
+            stray_funds: msg.stray_funds.unwrap_or_default(),
+            renounced: false,
+            active: true,
+            outcome: Outcome::Pending,
+        };
+
+        let auction_id = NEXT_AUCTION_ID.load(deps.storage)?;
+        NEXT_AUCTION_ID.save(deps.storage, &(auction_id + 1))?;
+        AUCTIONS.save(deps.storage, auction_id, &base_info)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "create_auction")
+            .add_attribute("auction_id", auction_id.to_string())
+            .add_attribute("owner", base_info.owner.as_str()))
+    }
+
+    fn denom_rates(
+        api: &dyn Api,
+        bidding_denom: &str,
+        accepted: Vec<AcceptedDenom>,
+    ) -> Result<Vec<DenomRate>, ContractError> {
+        let mut rates: Vec<DenomRate> = Vec::with_capacity(accepted.len());
+        for accepted in accepted {
+            if accepted.denom.is_empty()
+                || accepted.denom == bidding_denom
+                || rates.iter().any(|rate| rate.denom == accepted.denom)
+            {
+                return Err(ContractError::InvalidAcceptedDenoms {});
+            }
+            let rate = match accepted.rate {
+                Rate::Fixed(rate) if rate.is_zero() => {
+                    return Err(ContractError::InvalidAcceptedDenoms {})
+                }
+                Rate::Fixed(rate) => RateSource::Fixed(rate),
+                Rate::Oracle { contract } => RateSource::Oracle(api.addr_validate(&contract)?),
+            };
+            rates.push(DenomRate {
+                denom: accepted.denom,
+                rate,
+            });
+        }
+
+        Ok(rates)
+    }
+
+    pub fn receive_cw20(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        msg: Cw20ReceiveMsg,
+    ) -> Result<Response, ContractError> {
+        let sender = deps.api.addr_validate(&msg.sender)?;
+
+        match from_binary(&msg.msg)? {
+            ReceiveMsg::Bid { auction_id } => {
+                let base_info = AUCTIONS.load(deps.storage, auction_id)?;
+                let funds = cw20_funds(&base_info, &info.sender, msg.amount);
+
+                place_bid(deps, env, auction_id, base_info, sender, funds)
+            }
+            ReceiveMsg::BidUnits {
+                auction_id,
+                quantity,
+                price,
+            } => {
+                let base_info = AUCTIONS.load(deps.storage, auction_id)?;
+                let funds = cw20_funds(&base_info, &info.sender, msg.amount);
+
+                place_unit_bid(
+                    deps, env, auction_id, base_info, sender, funds, quantity, price,
+                )
+            }
+            ReceiveMsg::CommitBid {
+                auction_id,
+                commitment,
+            } => {
+                let base_info = AUCTIONS.load(deps.storage, auction_id)?;
+                let funds = cw20_funds(&base_info, &info.sender, msg.amount);
+
+                commit(deps, env, auction_id, base_info, sender, funds, commitment)
+            }
+            ReceiveMsg::CreateAuction(auction) => {
+                let lot = Lot::Cw20 {
+                    contract: info.sender,
+                    amount: msg.amount,
+                };
+                create(deps, env, sender, auction, Some(lot))
+            }
+        }
+    }
+
+    /// Received CW20 tokens, if they're the auction's bidding asset.
+    fn cw20_funds(base_info: &BaseInfo, token: &Addr, amount: Uint128) -> Option<Coin> {
+        (base_info.bidding_asset == AssetKind::Cw20 && *token == base_info.bidding_denom).then(
+            || Coin {
+                denom: token.to_string(),
+                amount,
+            },
+        )
+    }
+
+    /// Attached native funds in a denom the auction accepts, if it's bid in native denoms.
+    fn native_funds(base_info: &BaseInfo, info: &MessageInfo) -> Option<Coin> {
+        match base_info.bidding_asset {
+            AssetKind::Native => info
+                .funds
+                .iter()
+                .find(|c| base_info.accepts(&c.denom))
+                .cloned(),
+            AssetKind::Cw20 => None,
+        }
+    }
+
+    pub fn bid(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        auction_id: u64,
+    ) -> Result<Response, ContractError> {
+        let base_info = AUCTIONS.load(deps.storage, auction_id)?;
+        let funds = native_funds(&base_info, &info);
+        // A bid in the wrong denom is reported as such rather than as stray funds
+        let stray = stray_funds(&base_info.stray_funds, &info, funds.as_ref());
+
+        let resp = place_bid(deps, env, auction_id, base_info, info.sender.clone(), funds)?;
+        Ok(refund_stray(resp, &info.sender, stray?))
+    }
+
+    /// Coins sent besides `used`, fails if `policy` rejects them.
+    fn stray_funds(
+        policy: &StrayFunds,
+        info: &MessageInfo,
+        used: Option<&Coin>,
+    ) -> Result<Vec<Coin>, ContractError> {
+        let stray: Vec<_> = info
+            .funds
+            .iter()
+            .filter(|coin| !coin.amount.is_zero() && used != Some(*coin))
+            .cloned()
+            .collect();
+
+        match (stray.first(), policy) {
+            (Some(coin), StrayFunds::Reject) => Err(ContractError::StrayFunds {
+                denom: coin.denom.clone(),
+            }),
+            _ => Ok(stray),
+        }
+    }
+
+    /// Sends `stray` coins back to `sender` with the response.
+    fn refund_stray(resp: Response, sender: &Addr, stray: Vec<Coin>) -> Response {
+        if stray.is_empty() {
+            return resp;
+        }
+
+        let refunded = stray
+            .iter()
+            .map(Coin::to_string)
+            .collect::<Vec<_>>()
+            .join(",");
+        resp.add_message(BankMsg::Send {
+            to_address: sender.to_string(),
+            amount: stray,
+        })
+        .add_attribute("stray_refunded", refunded)
+    }
+
+    pub fn bid_units(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        auction_id: u64,
+        quantity: u64,
+        price: Uint128,
+    ) -> Result<Response, ContractError> {
+        let base_info = AUCTIONS.load(deps.storage, auction_id)?;
+        let funds = native_funds(&base_info, &info);
+        let stray = stray_funds(&base_info.stray_funds, &info, funds.as_ref());
+
+        let resp = place_unit_bid(
+            deps,
+            env,
+            auction_id,
+            base_info,
+            info.sender.clone(),
+            funds,
+            quantity,
+            price,
+        )?;
+        Ok(refund_stray(resp, &info.sender, stray?))
+    }
+
+    /// Adds an order for `quantity` units at `price` each to the bid book. The whole cost is
+    /// deposited until close.
+    #[allow(clippy::too_many_arguments)]
+    fn place_unit_bid(
+        deps: DepsMut,
+        env: Env,
+        auction_id: u64,
+        base_info: BaseInfo,
+        bidder: Addr,
+        funds: Option<Coin>,
+        quantity: u64,
+        price: Uint128,
+    ) -> Result<Response, ContractError> {
+        let AuctionMode::MultiUnit { quantity: units } = base_info.mode else {
+            return Err(ContractError::WrongAuctionMode {});
+        };
+        ensure_bidding_open(&base_info, &env.block, &bidder)?;
+
+        if quantity == 0 || quantity > units {
+            return Err(ContractError::InvalidQuantity {});
+        }
+        let min_price = base_info.starting_price.max(Uint128::one());
+        if price < min_price {
+            return Err(ContractError::BidBelowMinimum {
+                min_bid: min_price.to_string(),
+            });
+        }
+
+        let funds = funds.ok_or(ContractError::InvalidDenom {
+            denom: base_info.bidding_denom.clone(),
+        })?;
+        let cost = price
+            .checked_mul(Uint128::from(quantity))
+            .map_err(StdError::overflow)?;
+        if funds.amount != cost {
+            return Err(ContractError::IncorrectDeposit {
+                expected: cost.to_string(),
+            });
+        }
+
+        let seq = BID_SEQ
+            .may_load(deps.storage, auction_id)?
+            .unwrap_or_default();
+        BID_SEQ.save(deps.storage, auction_id, &(seq + 1))?;
+        BID_BOOK.save(
+            deps.storage,
+            (auction_id, price.u128(), u64::MAX - seq),
+            &UnitBid {
+                bidder: bidder.clone(),
+                quantity,
+                price,
+            },
+        )?;
+
+        record_bid(
+            deps.storage,
+            &env.block,
+            auction_id,
+            &bidder,
+            funds.clone(),
+            Uint128::zero(),
+        )?;
+
+        let deposit = bids().may_load(deps.storage, (auction_id, bidder.clone()))?;
+        let amount = deposit.map_or(cost, |d| d.amount + cost);
+        bids().save(
+            deps.storage,
+            (auction_id, bidder.clone()),
+            &Coin {
+                denom: funds.denom.clone(),
+                amount,
+            },
+        )?;
+        super::hold(deps.storage, &base_info, &funds)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "bid")
+            .add_attribute("auction_id", auction_id.to_string())
+            .add_attribute("sender", bidder.as_str())
+            .add_attribute("quantity", quantity.to_string())
+            .add_attribute("price", price))
+    }
+
+    pub fn commit_bid(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        auction_id: u64,
+        commitment: HexBinary,
+    ) -> Result<Response, ContractError> {
+        let base_info = AUCTIONS.load(deps.storage, auction_id)?;
+        let funds = native_funds(&base_info, &info);
+        let stray = stray_funds(&base_info.stray_funds, &info, funds.as_ref());
+
+        let resp = commit(
+            deps,
+            env,
+            auction_id,
+            base_info,
+            info.sender.clone(),
+            funds,
+            commitment,
+        )?;
+        Ok(refund_stray(resp, &info.sender, stray?))
+    }
+
+    /// Checks the auction accepts bids from `bidder` at this block.
+    fn ensure_bidding_open(
+        base_info: &BaseInfo,
+        block: &BlockInfo,
+        bidder: &Addr,
+    ) -> Result<(), ContractError> {
+        if !base_info.active {
+            return Err(ContractError::AuctionClosed {});
+        }
+
+        if let Some(start) = base_info.start {
+            if !start.is_triggered(block) {
+                return Err(ContractError::AuctionNotStarted {});
+            }
+        }
+
+        if base_info.has_ended(block) {
+            return Err(ContractError::AuctionEnded {});
+        }
+
+        if *bidder == base_info.owner {
+            return Err(ContractError::BiddingByOwner {});
+        }
+
+        Ok(())
+    }
+
+    /// Records a sealed bid commitment, `funds` are added to the bidder's deposit. Committing
+    /// again replaces the commitment.
+    fn commit(
+        deps: DepsMut,
+        env: Env,
+        auction_id: u64,
+        base_info: BaseInfo,
+        bidder: Addr,
+        funds: Option<Coin>,
+        commitment: HexBinary,
+    ) -> Result<Response, ContractError> {
+        if !matches!(base_info.mode, AuctionMode::Sealed { .. }) {
+            return Err(ContractError::WrongAuctionMode {});
+        }
+        ensure_bidding_open(&base_info, &env.block, &bidder)?;
+
+        let funds = funds.ok_or(ContractError::InvalidDenom {
+            denom: base_info.bidding_denom.clone(),
+        })?;
+
+        let deposit = bids().may_load(deps.storage, (auction_id, bidder.clone()))?;
+        let amount = deposit.map_or(funds.amount, |d| d.amount + funds.amount);
+        bids().save(
+            deps.storage,
+            (auction_id, bidder.clone()),
+            &Coin {
+                denom: funds.denom.clone(),
+                amount,
+            },
+        )?;
+        super::hold(deps.storage, &base_info, &funds)?;
+        // A new commitment replaces the previous one, and its place on ties with it
+        let seq = BID_SEQ
+            .may_load(deps.storage, auction_id)?
+            .unwrap_or_default();
+        BID_SEQ.save(deps.storage, auction_id, &(seq + 1))?;
+        SEALED_BIDS.save(
+            deps.storage,
+            (auction_id, bidder.clone()),
+            &SealedBid {
+                commitment,
+                revealed: None,
+                seq,
+            },
+        )?;
+
+        Ok(Response::new()
+            .add_attribute("action", "commit_bid")
+            .add_attribute("auction_id", auction_id.to_string())
+            .add_attribute("sender", bidder.as_str())
+            .add_attribute("deposit", amount))
+    }
+
+    /// Opens a sealed bid. Commission is charged on `amount`, the rest of the deposit goes back
+    /// to the bidder.
+    pub fn reveal_bid(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        auction_id: u64,
+        amount: Uint128,
+        salt: String,
+    ) -> Result<Response, ContractError> {
+        let base_info = AUCTIONS.load(deps.storage, auction_id)?;
+        let stray = stray_funds(&base_info.stray_funds, &info, None)?;
+        let AuctionMode::Sealed { reveal_end, .. } = base_info.mode else {
+            return Err(ContractError::WrongAuctionMode {});
+        };
+
+        if !base_info.active
+            || !base_info.has_ended(&env.block)
+            || reveal_end.is_triggered(&env.block)
+        {
+            return Err(ContractError::NotRevealPhase {});
+        }
+
+        let key = (auction_id, info.sender.clone());
+        let mut sealed = SEALED_BIDS
+            .may_load(deps.storage, key.clone())?
+            .ok_or(ContractError::InvalidBidReveal {})?;
+        let deposit = bids().load(deps.storage, key.clone())?;
+
+        if sealed.revealed.is_some()
+            || bid_commitment(&info.sender, amount, &salt) != sealed.commitment
+            || amount > deposit.amount
+        {
+            return Err(ContractError::InvalidBidReveal {});
+        }
+
+        let tax = bid_commission(&base_info, amount);
+        let net = amount.checked_sub(tax).map_err(StdError::overflow)?;
+        let min_bid = base_info.starting_price.max(Uint128::one());
+        if net < min_bid {
+            return Err(ContractError::BidBelowMinimum {
+                min_bid: min_bid.to_string(),
+            });
+        }
+
+        sealed.revealed = Some(amount);
+        SEALED_BIDS.save(deps.storage, key.clone(), &sealed)?;
+        record_bid(
+            deps.storage,
+            &env.block,
+            auction_id,
+            &info.sender,
+            Coin {
+                denom: deposit.denom.clone(),
+                amount,
+            },
+            tax,
+        )?;
+        bids().save(
+            deps.storage,
+            key,
+            &Coin {
+                denom: deposit.denom.clone(),
+                amount: net,
+            },
+        )?;
+        super::release(
+            deps.storage,
+            &base_info,
+            &Coin {
+                denom: deposit.denom.clone(),
+                amount: deposit.amount - net,
+            },
+        )?;
+
+        let mut resp = refund_stray(Response::new(), &info.sender, stray);
+        resp = pay_commission(resp, &base_info, &deposit.denom, tax)?;
+        let refund = deposit.amount - amount;
+        if !refund.is_zero() {
+            resp = resp.add_message(send_funds(
+                &base_info.bidding_asset,
+                &info.sender,
+                Coin {
+                    denom: deposit.denom,
+                    amount: refund,
+                },
+            )?);
+        }
+
+        Ok(resp
+            .add_attribute("action", "reveal_bid")
+            .add_attribute("auction_id", auction_id.to_string())
+            .add_attribute("sender", info.sender.as_str())
+            .add_attribute("amount", amount)
+            .add_attribute("commission", tax.to_string()))
+    }
+
+    /// Picks the highest revealed sealed bids as the leader and runner-up, earliest commitment
+    /// first on ties.
+    fn settle_sealed(deps: &mut DepsMut, auction_id: u64) -> StdResult<()> {
+        let mut revealed = SEALED_BIDS
+            .prefix(auction_id)
+            .range(deps.storage, None, None, Order::Ascending)
+            .filter_map(|entry| match entry {
+                Ok((bidder, sealed)) => sealed
+                    .revealed
+                    .map(|amount| Ok((bidder, amount, sealed.seq))),
+                Err(err) => Some(Err(err)),
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+        revealed.sort_by(|(_, a, a_seq), (_, b, b_seq)| b.cmp(a).then(a_seq.cmp(b_seq)));
+
+        let mut ranked = revealed.into_iter();
+        if let Some((address, amount, _)) = ranked.next() {
+            let denom = bids()
+                .load(deps.storage, (auction_id, address.clone()))?
+                .denom;
+            HIGHEST_BID.save(
+                deps.storage,
+                auction_id,
+                &Bid {
+                    address,
+                    bid: Coin { denom, amount },
+                },
+            )?;
+        }
+        if let Some((address, ..)) = ranked.next() {
+            let bid = bids().load(deps.storage, (auction_id, address.clone()))?;
+            RUNNER_UP.save(deps.storage, auction_id, &Bid { address, bid })?;
+        }
+
+        Ok(())
+    }
+
+    /// Places a bid of `funds`, `None` if the bidder didn't send the bidding asset.
+    fn place_bid(
+        deps: DepsMut,
+        env: Env,
+        auction_id: u64,
+        mut base_info: BaseInfo,
+        bidder: Addr,
+        funds: Option<Coin>,
+    ) -> Result<Response, ContractError> {
+        let mut resp = Response::new();
+
+        match base_info.mode {
+            AuctionMode::English => {}
+            AuctionMode::Dutch { .. } => {
+                return buy_dutch(deps, env, auction_id, base_info, bidder, funds)
+            }
+            AuctionMode::Sealed { .. } | AuctionMode::MultiUnit { .. } => {
+                return Err(ContractError::WrongAuctionMode {})
+            }
+        }
+        ensure_bidding_open(&base_info, &env.block, &bidder)?;
+
+        match funds {
+            Some(mut funds) => {
+                // Only the part of the bid needed for the buy-now price is taxed and deposited
+                let mut unused = Uint128::zero();
+                if let Some(buy_now) = base_info.buy_now_price {
+                    let current = bids()
+                        .may_load(deps.storage, (auction_id, bidder.clone()))?
+                        .map_or(Uint128::zero(), |bid| bid.amount);
+                    let needed = buy_now_bid(&base_info, buy_now.saturating_sub(current))?;
+                    if let Some(needed) = needed.filter(|needed| funds.amount > *needed) {
+                        unused = funds.amount - needed;
+                        funds.amount = needed;
+                    }
+                }
+
+                let tax = bid_commission(&base_info, funds.amount);
+                let remainder = funds.amount.checked_sub(tax).map_err(StdError::overflow)?;
+                // Bids in other denoms count at their value in the bidding denom
+                let value = remainder * denom_rate(deps.as_ref(), &base_info, &funds.denom)?;
+
+                let bid = bids().may_load(deps.storage, (auction_id, bidder.clone()))?;
+                let amount = bid.map_or(value, |b| b.amount + value);
+
+                // Reaching the buy-now price wins even when the increment would overshoot it
+                let min_bid = super::min_next_bid(deps.storage, auction_id, &base_info)?;
+                let buys_now = base_info.buy_now_price.is_some_and(|price| amount >= price);
+                if amount < min_bid && !buys_now {
+                    return Err(ContractError::BidBelowMinimum {
+                        min_bid: min_bid.to_string(),
+                    });
+                }
+                bids().save(
+                    deps.storage,
+                    (auction_id, bidder.clone()),
+                    &Coin {
+                        denom: base_info.bidding_denom.clone(),
+                        amount,
+                    },
+                )?;
+                if !base_info.accepted_denoms.is_empty() {
+                    DEPOSITS.update(
+                        deps.storage,
+                        (auction_id, bidder.clone(), funds.denom.clone()),
+                        |held| -> StdResult<_> { Ok(held.unwrap_or_default() + remainder) },
+                    )?;
+                }
+                super::hold(
+                    deps.storage,
+                    &base_info,
+                    &Coin {
+                        denom: funds.denom.clone(),
+                        amount: remainder,
+                    },
+                )?;
+                record_bid(
+                    deps.storage,
+                    &env.block,
+                    auction_id,
+                    &bidder,
+                    funds.clone(),
+                    tax,
+                )?;
+
+                // A displaced leader becomes the runner-up, raising one's own lead doesn't
+                if let Some(leader) = HIGHEST_BID.may_load(deps.storage, auction_id)? {
+                    if leader.address != bidder {
+                        let bid =
+                            bids().load(deps.storage, (auction_id, leader.address.clone()))?;
+
+                        if base_info.refund_on_outbid {
+                            let held = take_deposit(
+                                deps.storage,
+                                auction_id,
+                                &base_info,
+                                &leader.address,
+                                bid.clone(),
+                            )?;
+                            for coin in held {
+                                resp = resp.add_message(send_funds(
+                                    &base_info.bidding_asset,
+                                    &leader.address,
+                                    coin,
+                                )?);
+                            }
+                            resp = resp.add_attribute("refunded", leader.address.as_str());
+                        }
+
+                        RUNNER_UP.save(
+                            deps.storage,
+                            auction_id,
+                            &Bid {
+                                address: leader.address,
+                                bid,
+                            },
+                        )?;
+                    }
+                }
+
+                HIGHEST_BID.save(
+                    deps.storage,
+                    auction_id,
+                    &Bid {
+                        address: bidder.clone(),
+                        bid: funds.clone(),
+                    },
+                )?;
+
+                resp = pay_commission(resp, &base_info, &funds.denom, tax)?;
+
+                resp = resp
+                    .add_attribute("action", "bid")
+                    .add_attribute("auction_id", auction_id.to_string())
+                    .add_attribute("sender", bidder.as_str())
+                    .add_attribute("commission", tax.to_string());
+
+                // Reaching the buy-now price wins right away, the deposit above it is refunded
+                if let Some(buy_now) = base_info.buy_now_price.filter(|price| amount >= *price) {
+                    // Commission rounding can leave the deposit a little above the price
+                    let excess = amount - buy_now;
+                    if !excess.is_zero() {
+                        bids().save(
+                            deps.storage,
+                            (auction_id, bidder.clone()),
+                            &Coin {
+                                denom: base_info.bidding_denom.clone(),
+                                amount: buy_now,
+                            },
+                        )?;
+                        super::release(
+                            deps.storage,
+                            &base_info,
+                            &Coin {
+                                denom: base_info.bidding_denom.clone(),
+                                amount: excess,
+                            },
+                        )?;
+                    }
+                    let refund = Coin {
+                        denom: base_info.bidding_denom.clone(),
+                        amount: excess + unused,
+                    };
+                    if !refund.amount.is_zero() {
+                        resp = resp
+                            .add_attribute("refund", refund.amount)
+                            .add_message(send_funds(&base_info.bidding_asset, &bidder, refund)?);
+                    }
+
+                    let settled = settle(deps, auction_id, base_info, None)?;
+                    return Ok(resp
+                        .add_submessages(settled.messages)
+                        .add_attributes(settled.attributes)
+                        .add_attribute("buy_now", buy_now)
+                        .add_attribute("bidding", "closed"));
+                }
+
+                if let Some(end) = extend_end(&mut base_info, &env.block)? {
+                    AUCTIONS.save(deps.storage, auction_id, &base_info)?;
+                    resp = resp.add_attribute("end_extended_to", end.to_string());
+                }
+
+                Ok(resp)
+            }
+            None => Err(ContractError::InvalidDenom {
+                denom: base_info.bidding_denom,
+            }),
+        }
+    }
+
+    /// Buys the lot of a Dutch auction at the current price, anything sent above it is
+    /// refunded. The auction is settled right away.
+    fn buy_dutch(
+        deps: DepsMut,
+        env: Env,
+        auction_id: u64,
+        base_info: BaseInfo,
+        bidder: Addr,
+        funds: Option<Coin>,
+    ) -> Result<Response, ContractError> {
+        ensure_bidding_open(&base_info, &env.block, &bidder)?;
+
+        let funds = funds.ok_or(ContractError::InvalidDenom {
+            denom: base_info.bidding_denom.clone(),
+        })?;
+        let price = super::dutch_price(&base_info, &env.block).unwrap_or_default();
+        if funds.amount < price {
+            return Err(ContractError::BidBelowMinimum {
+                min_bid: price.to_string(),
+            });
+        }
+
+        let tax = bid_commission(&base_info, price);
+        record_bid(
+            deps.storage,
+            &env.block,
+            auction_id,
+            &bidder,
+            Coin {
+                denom: funds.denom.clone(),
+                amount: price,
+            },
+            tax,
+        )?;
+        let deposit = Coin {
+            denom: funds.denom.clone(),
+            amount: price.checked_sub(tax).map_err(StdError::overflow)?,
+        };
+        bids().save(deps.storage, (auction_id, bidder.clone()), &deposit)?;
+        super::hold(deps.storage, &base_info, &deposit)?;
+        HIGHEST_BID.save(
+            deps.storage,
+            auction_id,
+            &Bid {
+                address: bidder.clone(),
+                bid: Coin {
+                    denom: funds.denom.clone(),
+                    amount: price,
+                },
+            },
+        )?;
+
+        let mut resp = Response::new();
+        resp = pay_commission(resp, &base_info, &funds.denom, tax)?;
+        let excess = funds.amount - price;
+        if !excess.is_zero() {
+            resp = resp.add_message(send_funds(
+                &base_info.bidding_asset,
+                &bidder,
+                Coin {
+                    denom: funds.denom,
+                    amount: excess,
+                },
+            )?);
+        }
+
+        let settled = settle(deps, auction_id, base_info, None)?;
+
+        Ok(resp
+            .add_submessages(settled.messages)
+            .add_attributes(settled.attributes)
+            .add_attribute("action", "bid")
+            .add_attribute("auction_id", auction_id.to_string())
+            .add_attribute("sender", bidder.as_str())
+            .add_attribute("commission", tax.to_string())
+            .add_attribute("bidding", "closed"))
+    }
+
+    /// Value of one unit of the accepted `denom` in the bidding denom.
+    fn denom_rate(deps: Deps, base_info: &BaseInfo, denom: &str) -> StdResult<Decimal> {
+        let accepted = base_info
+            .accepted_denoms
+            .iter()
+            .find(|accepted| accepted.denom == denom);
+
+        match accepted.map(|accepted| &accepted.rate) {
+            None => Ok(Decimal::one()),
+            Some(RateSource::Fixed(rate)) => Ok(*rate),
+            Some(RateSource::Oracle(oracle)) => {
+                let resp: ExchangeRateResponse = deps.querier.query_wasm_smart(
+                    oracle,
+                    &OracleQueryMsg::ExchangeRate {
+                        base: denom.to_string(),
+                        quote: base_info.bidding_denom.clone(),
+                    },
+                )?;
+                Ok(resp.rate)
+            }
+        }
+    }
+
+    /// Removes the deposit `bid` of `bidder`, returning the coins held for it.
+    fn take_deposit(
+        storage: &mut dyn Storage,
+        auction_id: u64,
+        base_info: &BaseInfo,
+        bidder: &Addr,
+        bid: Coin,
+    ) -> StdResult<Vec<Coin>> {
+        let held = super::held_coins(storage, auction_id, base_info, bidder, bid)?;
+        bids().remove(storage, (auction_id, bidder.clone()))?;
+        for coin in &held {
+            if !base_info.accepted_denoms.is_empty() {
+                DEPOSITS.remove(storage, (auction_id, bidder.clone(), coin.denom.clone()));
+            }
+            super::release(storage, base_info, coin)?;
+        }
+
+        Ok(held)
+    }
+
+    /// Appends a bid of `gross`, `commission` of which was charged, to the bid history.
+    fn record_bid(
+        storage: &mut dyn Storage,
+        block: &BlockInfo,
+        auction_id: u64,
+        bidder: &Addr,
+        gross: Coin,
+        commission: Uint128,
+    ) -> StdResult<()> {
+        let id = BID_HISTORY_LEN
+            .may_load(storage, auction_id)?
+            .unwrap_or_default();
+        BID_HISTORY_LEN.save(storage, auction_id, &(id + 1))?;
+
+        BID_HISTORY.save(
+            storage,
+            (auction_id, id),
+            &BidHistoryEntry {
+                id,
+                address: bidder.clone(),
+                net: Coin {
+                    denom: gross.denom.clone(),
+                    amount: gross.amount - commission,
+                },
+                commission: Coin {
+                    denom: gross.denom.clone(),
+                    amount: commission,
+                },
+                gross,
+                height: block.height,
+                time: block.time,
+            },
+        )
+    }
+
+    /// Smallest bid leaving at least `net` after commission, `None` if commission takes it all.
+    fn buy_now_bid(base_info: &BaseInfo, net: Uint128) -> StdResult<Option<Uint128>> {
+        let kept = match base_info.commission_mode {
+            CommissionMode::PerBid => Decimal::one() - base_info.commission,
+            CommissionMode::OnSettlement => return Ok(Some(net)),
+        };
+        if kept.is_zero() {
+            return Ok(None);
+        }
+
+        // Commission is rounded down, so rounding this up can overshoot by a unit
+        let kept = Uint256::from(kept.atomics());
+        let mut gross: Uint128 = (net.full_mul(Decimal::one().atomics()) + kept - Uint256::one())
+            .checked_div(kept)?
+            .try_into()?;
+        while let Ok(less) = gross.checked_sub(Uint128::one()) {
+            if less - bid_commission(base_info, less) < net {
+                break;
+            }
+            gross = less;
+        }
+
+        Ok(Some(gross))
+    }
+
+    /// Commission taken from a bid as it's placed, none if it's charged at settlement.
+    fn bid_commission(base_info: &BaseInfo, amount: Uint128) -> Uint128 {
+        match base_info.commission_mode {
+            CommissionMode::PerBid => amount * base_info.commission,
+            CommissionMode::OnSettlement => Uint128::zero(),
+        }
+    }
+
+    /// Splits `commission` between the fee recipients by weight, the first one gets the
+    /// rounding dust. Without fee recipients it all goes to the owner.
+    fn pay_commission(
+        mut resp: Response,
+        base_info: &BaseInfo,
+        denom: &str,
+        commission: Uint128,
+    ) -> StdResult<Response> {
+        if commission.is_zero() {
+            return Ok(resp);
+        }
+
+        let owner_share = [FeeShare {
+            address: base_info.owner.clone(),
+            weight: 1,
+        }];
+        let recipients = match base_info.fee_recipients.as_slice() {
+            [] => &owner_share[..],
+            recipients => recipients,
+        };
+
+        let total_weight: u64 = recipients.iter().map(|r| u64::from(r.weight)).sum();
+        let shares: Vec<Uint128> = recipients
+            .iter()
+            .map(|r| commission.multiply_ratio(r.weight, total_weight))
+            .collect();
+        let dust = commission - shares.iter().copied().sum::<Uint128>();
+
+        for (index, (recipient, share)) in recipients.iter().zip(shares).enumerate() {
+            let amount = if index == 0 { share + dust } else { share };
+            if amount.is_zero() {
+                continue;
+            }
+            resp = resp
+                .add_message(send_funds(
+                    &base_info.bidding_asset,
+                    &recipient.address,
+                    Coin {
+                        denom: denom.to_string(),
+                        amount,
+                    },
+                )?)
+                .add_attribute("fee_recipient", recipient.address.as_str())
+                .add_attribute("fee_amount", amount);
+        }
+
+        Ok(resp)
+    }
+
+    /// Pays out `funds` of the bidding asset, as a bank send or a CW20 transfer.
+    fn send_funds(asset: &AssetKind, recipient: &Addr, funds: Coin) -> StdResult<CosmosMsg> {
+        match asset {
+            AssetKind::Native => Ok(BankMsg::Send {
+                to_address: recipient.to_string(),
+                amount: vec![funds],
+            }
+            .into()),
+            AssetKind::Cw20 => Ok(WasmMsg::Execute {
+                contract_addr: funds.denom,
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: recipient.to_string(),
+                    amount: funds.amount,
+                })?,
+                funds: vec![],
+            }
+            .into()),
+        }
+    }
+
+    /// Pushes the effective end back if the bid landed within the anti-sniping window.
+    fn extend_end(base_info: &mut BaseInfo, block: &BlockInfo) -> StdResult<Option<Scheduled>> {
+        let (Some(anti_sniping), Some(end)) = (&base_info.anti_sniping, base_info.effective_end)
+        else {
+            return Ok(None);
+        };
+
+        let in_window = match (end, anti_sniping.window) {
+            (Scheduled::AtHeight(height), Duration::Height(window)) => {
+                block.height + window >= height
+            }
+            (Scheduled::AtTime(time), Duration::Time(window)) => {
+                block.time.plus_seconds(window) >= time
+            }
+            _ => false,
+        };
+        if !in_window {
+            return Ok(None);
+        }
+
+        let extended = (end + anti_sniping.extension)?;
+        base_info.effective_end = Some(extended);
+        Ok(Some(extended))
+    }
+
+    /// Whether `amount` meets the auction reserve. A hidden reserve which isn't revealed
+    /// is never met.
+    fn reserve_met(
+        base_info: &BaseInfo,
+        amount: Uint128,
+        reveal: Option<&ReserveReveal>,
+    ) -> Result<bool, ContractError> {
+        match (&base_info.reserve, reveal) {
+            (None, _) => Ok(true),
+            (Some(Reserve::Public { amount: reserve }), _) => Ok(amount >= *reserve),
+            (Some(Reserve::Hidden { commitment }), Some(reveal)) => {
+                if reserve_commitment(reveal.amount, &reveal.salt) != *commitment {
+                    return Err(ContractError::InvalidReserveReveal {});
+                }
+                Ok(amount >= reveal.amount)
+            }
+            (Some(Reserve::Hidden { .. }), None) => Ok(false),
+        }
+    }
+
+    /// Fills the bid book from the best price down until the units run out. Every accepted
+    /// order pays the lowest accepted price, the rest of all deposits is refunded.
+    fn settle_units(
+        storage: &mut dyn Storage,
+        auction_id: u64,
+        base_info: &mut BaseInfo,
+        quantity: u64,
+    ) -> Result<Response, ContractError> {
+        let mut resp = Response::new();
+
+        let orders = BID_BOOK
+            .sub_prefix(auction_id)
+            .range(storage, None, None, Order::Descending)
+            .map(|order| order.map(|(_, order)| order))
+            .collect::<StdResult<Vec<_>>>()?;
+
+        let mut remaining = quantity;
+        let mut clearing_price = None;
+        for order in orders {
+            if remaining == 0 {
+                break;
+            }
+            let units = order.quantity.min(remaining);
+            remaining -= units;
+            clearing_price = Some(order.price);
+            ALLOCATIONS.update(storage, (auction_id, order.bidder), |allocated| {
+                StdResult::Ok(allocated.unwrap_or_default() + units)
+            })?;
+        }
+
+        let Some(clearing_price) = clearing_price else {
+            base_info.outcome = Outcome::NoBids;
+            return Ok(resp.add_attribute("winner", "None"));
+        };
+        base_info.outcome = Outcome::Sold;
+        CLEARING_PRICE.save(storage, auction_id, &clearing_price)?;
+
+        let deposits = bids()
+            .prefix(auction_id)
+            .range(storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+
+        let mut proceeds = Uint128::zero();
+        for (bidder, deposit) in deposits {
+            let units = ALLOCATIONS
+                .may_load(storage, (auction_id, bidder.clone()))?
+                .unwrap_or_default();
+            let cost = clearing_price * Uint128::from(units);
+            proceeds += cost;
+
+            bids().remove(storage, (auction_id, bidder.clone()))?;
+            super::release(storage, base_info, &deposit)?;
+            let refund = deposit.amount - cost;
+            if !refund.is_zero() {
+                resp = resp.add_message(send_funds(
+                    &base_info.bidding_asset,
+                    &bidder,
+                    Coin {
+                        denom: deposit.denom,
+                        amount: refund,
+                    },
+                )?);
+            }
+        }
+
+        let commission = proceeds * base_info.commission;
+        resp = pay_commission(resp, base_info, &base_info.bidding_denom, commission)?;
+        resp = resp
+            .add_message(send_funds(
+                &base_info.bidding_asset,
+                &base_info.owner,
+                Coin {
+                    denom: base_info.bidding_denom.clone(),
+                    amount: proceeds
+                        .checked_sub(commission)
+                        .map_err(StdError::overflow)?,
+                },
+            )?)
+            .add_attribute("clearing_price", clearing_price)
+            .add_attribute("units_sold", (quantity - remaining).to_string())
+            .add_attribute("commission", commission);
+
+        Ok(resp)
+    }
+
+    /// Price a second-price winner pays: the runner-up bid, raised to the starting price and
+    /// the reserve. The reserve reveal must already be verified.
+    fn second_price(
+        storage: &dyn Storage,
+        auction_id: u64,
+        base_info: &BaseInfo,
+        reveal: Option<&ReserveReveal>,
+    ) -> StdResult<Uint128> {
+        let runner_up = RUNNER_UP
+            .may_load(storage, auction_id)?
+            .map_or(Uint128::zero(), |runner_up| runner_up.bid.amount);
+        let reserve = match (&base_info.reserve, reveal) {
+            (Some(Reserve::Public { amount }), _) => *amount,
+            (Some(Reserve::Hidden { .. }), Some(reveal)) => reveal.amount,
+            _ => Uint128::zero(),
+        };
+
+        Ok(runner_up.max(base_info.starting_price).max(reserve))
+    }
+
+    /// Creator royalty owed on selling `lot` for `price`. `None` if the lot isn't an NFT, its
+    /// contract doesn't implement CW2981 or reports no royalty.
+    fn lot_royalty(deps: Deps, lot: &Lot, price: &Coin) -> Option<Royalty> {
+        let (contract, token_id) = match lot {
+            Lot::Cw721 { contract, token_id } => (contract, token_id),
+            Lot::Cw20 { .. } => return None,
+        };
+
+        let info: RoyaltiesInfoResponse = deps
+            .querier
+            .query_wasm_smart(
+                contract,
+                &Cw2981QueryMsg::Extension {
+                    msg: RoyaltyQueryMsg::RoyaltyInfo {
+                        token_id: token_id.clone(),
+                        sale_price: price.amount,
+                    },
+                },
+            )
+            .ok()?;
+        if info.royalty_amount.is_zero() {
+            return None;
+        }
+
+        Some(Royalty {
+            recipient: deps.api.addr_validate(&info.address).ok()?,
+            amount: Coin {
+                denom: price.denom.clone(),
+                amount: info.royalty_amount,
+            },
+        })
+    }
+
+    fn transfer_lot(lot: &Lot, recipient: &Addr) -> StdResult<CosmosMsg> {
+        match lot {
+            Lot::Cw721 { contract, token_id } => Ok(WasmMsg::Execute {
+                contract_addr: contract.to_string(),
+                msg: to_binary(&Cw721ExecuteMsg::TransferNft {
+                    recipient: recipient.to_string(),
+                    token_id: token_id.clone(),
+                })?,
+                funds: vec![],
+            }
+            .into()),
+            Lot::Cw20 { contract, amount } => Ok(WasmMsg::Execute {
+                contract_addr: contract.to_string(),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: recipient.to_string(),
+                    amount: *amount,
+                })?,
+                funds: vec![],
+            }
+            .into()),
+        }
+    }
 
-    use crate::error::ContractError;
-    use crate::state::{Bid, BASE_INFO, BIDS, HIGHEST_BID};
+    pub fn close(
+        mut deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        auction_id: u64,
+        reveal: Option<ReserveReveal>,
+    ) -> Result<Response, ContractError> {
+        let base_info = AUCTIONS.load(deps.storage, auction_id)?;
+        let stray = stray_funds(&base_info.stray_funds, &info, None)?;
 
-    pub fn bid(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
-        let mut resp = Response::new();
+        // Once the scheduled end has passed anybody can close the auction
+        if !base_info.has_ended(&env.block) {
+            ensure_owner(&base_info, &info.sender)?;
+        }
 
-        let base_info = BASE_INFO.load(deps.storage)?;
         if !base_info.active {
             return Err(ContractError::AuctionClosed {});
         }
 
-        if info.sender == base_info.owner {
-            return Err(ContractError::BiddingByOwner {});
+        // Only the owner can give up on a hidden reserve, anybody else has to reveal it
+        // until the grace period is over, so an absent owner can't keep the leader's bid locked
+        if matches!(base_info.reserve, Some(Reserve::Hidden { .. }))
+            && reveal.is_none()
+            && ensure_owner(&base_info, &info.sender).is_err()
+            && !reveal_grace_over(&base_info, &env.block)?
+        {
+            return Err(ContractError::ReserveNotRevealed {});
         }
 
-        match info
-            .funds
-            .iter()
-            .find(|c| c.denom == base_info.bidding_denom)
-        {
-            Some(funds) => {
-                let tax = funds.amount * base_info.commission;
-                let remainder = funds.amount.checked_sub(tax).map_err(StdError::overflow)?;
+        if let AuctionMode::Sealed { reveal_end, .. } = base_info.mode {
+            if !reveal_end.is_triggered(&env.block) {
+                return Err(ContractError::RevealPhaseNotOver {});
+            }
+            settle_sealed(&mut deps, auction_id)?;
+        }
 
-                let bid = BIDS.may_load(deps.storage, info.sender.clone())?;
-                let amount = bid.map_or(remainder, |b| b.amount + remainder);
+        let resp = settle(deps, auction_id, base_info, reveal)?
+            .add_attribute("action", "close")
+            .add_attribute("auction_id", auction_id.to_string())
+            .add_attribute("sender", info.sender.as_str())
+            .add_attribute("bidding", "closed");
 
-                let highest_bid_amount = HIGHEST_BID
-                    .may_load(deps.storage)?
-                    .map(|b| b.bid.amount)
-                    .unwrap_or(Uint128::zero());
+        Ok(refund_stray(resp, &info.sender, stray))
+    }
 
-                if amount <= highest_bid_amount {
-                    return Err(ContractError::BidTooLow {
-                        highest_bid: highest_bid_amount.to_string(),
-                    });
-                }
-                BIDS.save(
-                    deps.storage,
-                    info.sender.clone(),
-                    &Coin {
-                        denom: funds.denom.clone(),
-                        amount,
-                    },
-                )?;
+    fn reveal_grace_over(base_info: &BaseInfo, block: &BlockInfo) -> StdResult<bool> {
+        let Some(end) = base_info.effective_end else {
+            return Ok(false);
+        };
+        let grace = match end {
+            Scheduled::AtHeight(_) => Duration::Height(super::RESERVE_REVEAL_GRACE_BLOCKS),
+            Scheduled::AtTime(_) => Duration::Time(super::RESERVE_REVEAL_GRACE_SECONDS),
+        };
+        Ok((end + grace)?.is_triggered(block))
+    }
 
-                HIGHEST_BID.save(
-                    deps.storage,
-                    &Bid {
-                        address: info.sender.clone(),
-                        bid: Coin {
+    /// Ends the auction: pays the creator royalty of an NFT lot and the owner, and hands the
+    /// lot over if the leader met the reserve. Returns the lot to the owner otherwise.
+    fn settle(
+        deps: DepsMut,
+        auction_id: u64,
+        mut base_info: BaseInfo,
+        reveal: Option<ReserveReveal>,
+    ) -> Result<Response, ContractError> {
+        let mut resp = Response::new();
+        base_info.active = false;
+
+        if let AuctionMode::MultiUnit { quantity } = base_info.mode {
+            let resp = settle_units(deps.storage, auction_id, &mut base_info, quantity)?;
+            AUCTIONS.save(deps.storage, auction_id, &base_info)?;
+            return Ok(resp);
+        }
+
+        // Without a sale the lot goes back to the owner
+        let mut lot_recipient = base_info.owner.clone();
+
+        let winner = HIGHEST_BID.may_load(deps.storage, auction_id)?;
+        match winner {
+            Some(winner) => {
+                let funds = bids().load(deps.storage, (auction_id, winner.address.clone()))?;
+
+                if reserve_met(&base_info, funds.amount, reveal.as_ref())? {
+                    base_info.outcome = Outcome::Sold;
+                    lot_recipient = winner.address.clone();
+
+                    let price = match base_info.settlement {
+                        Settlement::FirstPrice => funds.amount,
+                        Settlement::SecondPrice => {
+                            second_price(deps.storage, auction_id, &base_info, reveal.as_ref())?
+                                .min(funds.amount)
+                        }
+                    };
+
+                    // The winner's deposit is all paid out, to the owner or back as a refund
+                    let deposit = super::held_coins(
+                        deps.storage,
+                        auction_id,
+                        &base_info,
+                        &winner.address,
+                        funds.clone(),
+                    )?;
+                    for coin in &deposit {
+                        super::release(deps.storage, &base_info, coin)?;
+                    }
+
+                    // Only first-price auctions accept several denoms, paying the whole deposit
+                    let paid = super::held_coins(
+                        deps.storage,
+                        auction_id,
+                        &base_info,
+                        &winner.address,
+                        Coin {
                             denom: funds.denom.clone(),
-                            amount: funds.amount,
+                            amount: price,
                         },
-                    },
-                )?;
+                    )?;
 
-                let bank_msg = BankMsg::Send {
-                    to_address: base_info.owner.to_string(),
-                    amount: vec![Coin {
-                        denom: funds.denom.clone(),
-                        amount: tax,
-                    }],
-                };
+                    let mut sale = Sale {
+                        buyer: winner.address.clone(),
+                        price: vec![],
+                        commission: vec![],
+                        royalties: vec![],
+                        seller_proceeds: vec![],
+                    };
+                    for coin in paid {
+                        let commission = match base_info.commission_mode {
+                            CommissionMode::PerBid => Uint128::zero(),
+                            CommissionMode::OnSettlement => coin.amount * base_info.commission,
+                        };
+                        resp = pay_commission(resp, &base_info, &coin.denom, commission)?;
+                        let mut proceeds = coin
+                            .amount
+                            .checked_sub(commission)
+                            .map_err(StdError::overflow)?;
 
-                resp = resp
-                    .add_message(bank_msg)
-                    .add_attribute("action", "bid")
-                    .add_attribute("sender", info.sender.as_str())
-                    .add_attribute("commission", tax.to_string());
+                        // The creator is paid before the seller, never more than what's left
+                        let royalty = base_info
+                            .lot
+                            .as_ref()
+                            .and_then(|lot| lot_royalty(deps.as_ref(), lot, &coin));
+                        if let Some(mut royalty) = royalty {
+                            royalty.amount.amount = royalty.amount.amount.min(proceeds);
+                            if !royalty.amount.amount.is_zero() {
+                                proceeds -= royalty.amount.amount;
+                                resp = resp
+                                    .add_message(send_funds(
+                                        &base_info.bidding_asset,
+                                        &royalty.recipient,
+                                        royalty.amount.clone(),
+                                    )?)
+                                    .add_attribute("royalty_recipient", royalty.recipient.as_str())
+                                    .add_attribute("royalty_amount", royalty.amount.to_string());
+                                sale.royalties.push(royalty);
+                            }
+                        }
 
-                Ok(resp)
+                        let proceeds = Coin {
+                            denom: coin.denom.clone(),
+                            amount: proceeds,
+                        };
+                        if !proceeds.amount.is_zero() {
+                            resp = resp.add_message(send_funds(
+                                &base_info.bidding_asset,
+                                &base_info.owner,
+                                proceeds.clone(),
+                            )?);
+                        }
+                        if !commission.is_zero() {
+                            sale.commission.push(Coin {
+                                denom: coin.denom.clone(),
+                                amount: commission,
+                            });
+                        }
+                        sale.seller_proceeds.push(proceeds);
+                        sale.price.push(coin);
+                    }
+                    SALES.save(deps.storage, auction_id, &sale)?;
+
+                    resp = resp
+                        .add_attribute("winner", winner.address.as_str())
+                        .add_attribute("highest_bid", funds.amount)
+                        .add_attribute("price", price);
+
+                    let refund = funds.amount - price;
+                    if !refund.is_zero() {
+                        resp = resp
+                            .add_message(send_funds(
+                                &base_info.bidding_asset,
+                                &winner.address,
+                                Coin {
+                                    denom: funds.denom,
+                                    amount: refund,
+                                },
+                            )?)
+                            .add_attribute("refund", refund);
+                    }
+                } else {
+                    // No sale, every bidder including the top one can retract
+                    base_info.outcome = Outcome::ReserveNotMet;
+                    resp = resp
+                        .add_attribute("winner", "None")
+                        .add_attribute("reserve", "not_met");
+                }
             }
-            None => Err(ContractError::InvalidDenom {
-                denom: base_info.bidding_denom,
-            }),
+            None => {
+                base_info.outcome = Outcome::NoBids;
+                resp = resp.add_attribute("winner", "None");
+            }
+        }
+
+        if let Some(lot) = &base_info.lot {
+            resp = resp
+                .add_message(transfer_lot(lot, &lot_recipient)?)
+                .add_attribute("lot_recipient", lot_recipient.as_str());
         }
+
+        AUCTIONS.save(deps.storage, auction_id, &base_info)?;
+
+        Ok(resp)
     }
 
-    pub fn close(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
-        let mut base_info = BASE_INFO.load(deps.storage)?;
-        let mut resp = Response::new();
+    /// Refunds up to `limit` losing bidders of a closed auction.
+    pub fn refund_all(
+        deps: DepsMut,
+        info: MessageInfo,
+        auction_id: u64,
+        limit: Option<u32>,
+    ) -> Result<Response, ContractError> {
+        let base_info = AUCTIONS.load(deps.storage, auction_id)?;
+        let stray = stray_funds(&base_info.stray_funds, &info, None)?;
+        let mut resp = refund_stray(Response::new(), &info.sender, stray);
 
-        if info.sender != base_info.owner {
-            return Err(ContractError::Unauthorized {
-                owner: base_info.owner.to_string(),
-            });
+        if base_info.active {
+            return Err(ContractError::AuctionNotClosed {});
         }
 
-        if !base_info.active {
-            return Err(ContractError::AuctionClosed {});
+        let winner = match base_info.outcome {
+            Outcome::Sold => HIGHEST_BID
+                .may_load(deps.storage, auction_id)?
+                .map(|winner| winner.address),
+            _ => None,
+        };
+
+        // Refunded entries are removed, so every call starts from the beginning
+        let limit = limit.unwrap_or(super::DEFAULT_LIMIT).min(super::MAX_LIMIT) as usize;
+        let bids = bids()
+            .prefix(auction_id)
+            .range(deps.storage, None, None, Order::Ascending)
+            .filter(|bid| {
+                bid.as_ref()
+                    .map_or(true, |(bidder, _)| Some(bidder) != winner.as_ref())
+            })
+            .take(limit)
+            .collect::<StdResult<Vec<_>>>()?;
+
+        let refunded = bids.len();
+        for (bidder, bid) in bids {
+            resp = refund(
+                deps.storage,
+                auction_id,
+                &base_info,
+                &bidder,
+                &bidder,
+                bid,
+                Decimal::zero(),
+                resp,
+            )?;
         }
 
-        base_info.active = false;
+        Ok(resp
+            .add_attribute("action", "refund_all")
+            .add_attribute("auction_id", auction_id.to_string())
+            .add_attribute("refunded", refunded.to_string()))
+    }
 
-        let winner = HIGHEST_BID.may_load(deps.storage)?;
-        match winner {
-            Some(winner) => {
-                let funds = BIDS.load(deps.storage, winner.address.clone()).unwrap();
+    /// Largest deposit in the auction not made by `leader`.
+    fn best_other_bid(
+        storage: &dyn Storage,
+        auction_id: u64,
+        leader: &Addr,
+    ) -> StdResult<Option<Bid>> {
+        let best = bids()
+            .idx
+            .amount
+            .sub_prefix(auction_id)
+            .range(storage, None, None, Order::Descending)
+            .find(|bid| {
+                bid.as_ref()
+                    .map_or(true, |((_, address), _)| address != leader)
+            })
+            .transpose()?;
 
-                let bank_msg = BankMsg::Send {
-                    to_address: base_info.owner.to_string(),
-                    amount: vec![funds.clone()],
-                };
+        Ok(best.map(|((_, address), bid)| Bid { address, bid }))
+    }
+
+    /// Pays out the deposit `bid` of `bidder` to `receiver` and removes it, the `penalty`
+    /// share of every coin going to the owner. An unrevealed sealed bid forfeits its penalty
+    /// instead.
+    #[allow(clippy::too_many_arguments)]
+    fn refund(
+        storage: &mut dyn Storage,
+        auction_id: u64,
+        base_info: &BaseInfo,
+        bidder: &Addr,
+        receiver: &Addr,
+        bid: Coin,
+        mut penalty: Decimal,
+        mut resp: Response,
+    ) -> Result<Response, ContractError> {
+        let sealed = SEALED_BIDS.may_load(storage, (auction_id, bidder.clone()))?;
+        if let (
+            AuctionMode::Sealed {
+                unrevealed_penalty, ..
+            },
+            Some(SealedBid { revealed: None, .. }),
+        ) = (&base_info.mode, sealed)
+        {
+            penalty = *unrevealed_penalty;
+        }
 
+        for mut coin in take_deposit(storage, auction_id, base_info, bidder, bid)? {
+            let forfeit = coin.amount * penalty;
+            if !forfeit.is_zero() {
+                coin.amount -= forfeit;
                 resp = resp
-                    .add_message(bank_msg)
-                    .add_attribute("winner", winner.address.as_str())
-                    .add_attribute("highest_bid", funds.amount);
+                    .add_message(send_funds(
+                        &base_info.bidding_asset,
+                        &base_info.owner,
+                        Coin {
+                            denom: coin.denom.clone(),
+                            amount: forfeit,
+                        },
+                    )?)
+                    .add_attribute("penalty", forfeit);
             }
-            None => {
-                resp = resp.add_attribute("winner", "None");
+
+            if !coin.amount.is_zero() {
+                resp = resp.add_message(send_funds(&base_info.bidding_asset, receiver, coin)?);
             }
         }
 
-        BASE_INFO.save(deps.storage, &base_info)?;
+        Ok(resp)
+    }
 
-        resp = resp
-            .add_attribute("action", "close")
-            .add_attribute("sender", info.sender.as_str())
-            .add_attribute("bidding", "closed");
+    /// Checks `sender` controls the auction.
+    fn ensure_owner(base_info: &BaseInfo, sender: &Addr) -> Result<(), ContractError> {
+        if base_info.renounced {
+            return Err(ContractError::OwnershipRenounced {});
+        }
+        if *sender != base_info.owner {
+            return Err(ContractError::Unauthorized {
+                owner: base_info.owner.to_string(),
+            });
+        }
 
-        Ok(resp)
+        Ok(())
+    }
+
+    pub fn propose_new_owner(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        auction_id: u64,
+        new_owner: String,
+        expiry: Option<Scheduled>,
+    ) -> Result<Response, ContractError> {
+        let base_info = AUCTIONS.load(deps.storage, auction_id)?;
+        let stray = stray_funds(&base_info.stray_funds, &info, None)?;
+        ensure_owner(&base_info, &info.sender)?;
+
+        if expiry.is_some_and(|expiry| expiry.is_triggered(&env.block)) {
+            return Err(ContractError::InvalidSchedule {});
+        }
+
+        let address = deps.api.addr_validate(&new_owner)?;
+        PENDING_OWNER.save(
+            deps.storage,
+            auction_id,
+            &PendingOwner {
+                address: address.clone(),
+                expiry,
+            },
+        )?;
+
+        let resp = Response::new()
+            .add_attribute("action", "propose_new_owner")
+            .add_attribute("auction_id", auction_id.to_string())
+            .add_attribute("pending_owner", address.as_str());
+        Ok(refund_stray(resp, &info.sender, stray))
+    }
+
+    /// Hands the auction over to the proposed owner, including the proceeds and commission
+    /// still to be paid.
+    pub fn accept_ownership(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        auction_id: u64,
+    ) -> Result<Response, ContractError> {
+        let mut base_info = AUCTIONS.load(deps.storage, auction_id)?;
+        let stray = stray_funds(&base_info.stray_funds, &info, None)?;
+        let pending = PENDING_OWNER
+            .may_load(deps.storage, auction_id)?
+            .filter(|pending| pending.address == info.sender)
+            .ok_or(ContractError::NoOwnershipTransfer {})?;
+
+        if pending
+            .expiry
+            .is_some_and(|expiry| expiry.is_triggered(&env.block))
+        {
+            return Err(ContractError::OwnershipTransferExpired {});
+        }
+        if bids().has(deps.storage, (auction_id, info.sender.clone())) {
+            return Err(ContractError::BiddingByOwner {});
+        }
+
+        PENDING_OWNER.remove(deps.storage, auction_id);
+        let previous_owner = std::mem::replace(&mut base_info.owner, pending.address);
+        AUCTIONS.save(deps.storage, auction_id, &base_info)?;
+
+        let resp = Response::new()
+            .add_attribute("action", "accept_ownership")
+            .add_attribute("auction_id", auction_id.to_string())
+            .add_attribute("previous_owner", previous_owner.as_str())
+            .add_attribute("owner", base_info.owner.as_str());
+        Ok(refund_stray(resp, &info.sender, stray))
+    }
+
+    pub fn cancel_ownership_transfer(
+        deps: DepsMut,
+        info: MessageInfo,
+        auction_id: u64,
+    ) -> Result<Response, ContractError> {
+        let base_info = AUCTIONS.load(deps.storage, auction_id)?;
+        let stray = stray_funds(&base_info.stray_funds, &info, None)?;
+        ensure_owner(&base_info, &info.sender)?;
+
+        if !PENDING_OWNER.has(deps.storage, auction_id) {
+            return Err(ContractError::NoOwnershipTransfer {});
+        }
+        PENDING_OWNER.remove(deps.storage, auction_id);
+
+        let resp = Response::new()
+            .add_attribute("action", "cancel_ownership_transfer")
+            .add_attribute("auction_id", auction_id.to_string());
+        Ok(refund_stray(resp, &info.sender, stray))
+    }
+
+    /// Gives up control of the auction for good. The owner is still paid, but the auction
+    /// can only be closed once it has ended, so it must have an end.
+    pub fn renounce_ownership(
+        deps: DepsMut,
+        info: MessageInfo,
+        auction_id: u64,
+    ) -> Result<Response, ContractError> {
+        let mut base_info = AUCTIONS.load(deps.storage, auction_id)?;
+        let stray = stray_funds(&base_info.stray_funds, &info, None)?;
+        ensure_owner(&base_info, &info.sender)?;
+
+        if base_info.end.is_none() {
+            return Err(ContractError::RenounceWithoutEnd {});
+        }
+
+        base_info.renounced = true;
+        AUCTIONS.save(deps.storage, auction_id, &base_info)?;
+        PENDING_OWNER.remove(deps.storage, auction_id);
+
+        let resp = Response::new()
+            .add_attribute("action", "renounce_ownership")
+            .add_attribute("auction_id", auction_id.to_string());
+        Ok(refund_stray(resp, &info.sender, stray))
     }
 
     pub fn retract(
         deps: DepsMut,
         info: MessageInfo,
+        auction_id: u64,
         receiver: Option<String>,
     ) -> Result<Response, ContractError> {
-        let mut resp = Response::new();
-        let base_info = BASE_INFO.load(deps.storage)?;
+        let base_info = AUCTIONS.load(deps.storage, auction_id)?;
+        let stray = stray_funds(&base_info.stray_funds, &info, None)?;
+        let mut resp = refund_stray(Response::new(), &info.sender, stray);
 
-        if base_info.active {
+        // Bidders who aren't leading an English auction can withdraw before close
+        if base_info.active && base_info.mode != AuctionMode::English {
             return Err(ContractError::AuctionNotClosed {});
         }
 
-        let winner = HIGHEST_BID.may_load(deps.storage)?;
-        if let Some(winner) = winner {
-            if info.sender == winner.address {
-                return Err(ContractError::NoFundsToRetract {});
+        if base_info.active || base_info.outcome == Outcome::Sold {
+            let leader = HIGHEST_BID.may_load(deps.storage, auction_id)?;
+            if leader.is_some_and(|leader| info.sender == leader.address) {
+                return Err(if base_info.active {
+                    ContractError::LeadingBidLocked {}
+                } else {
+                    ContractError::NoFundsToRetract {}
+                });
             }
         }
 
-        let receiver_addr = receiver.unwrap_or(info.sender.to_string());
-        let bids = BIDS.may_load(deps.storage, info.sender)?;
-        match bids {
+        let receiver_addr = match receiver {
+            Some(receiver) => deps.api.addr_validate(&receiver)?,
+            None => info.sender.clone(),
+        };
+        let deposit = bids().may_load(deps.storage, (auction_id, info.sender.clone()))?;
+        let runner_up = RUNNER_UP.may_load(deps.storage, auction_id)?;
+        let runner_up_withdrawn =
+            base_info.active && runner_up.is_some_and(|runner_up| runner_up.address == info.sender);
+        match deposit {
             Some(bid) => {
-                let bank_msg = BankMsg::Send {
-                    to_address: receiver_addr.clone(),
-                    amount: vec![bid],
+                let penalty = if base_info.active {
+                    resp = resp.add_attribute("withdrawn", "active");
+                    base_info.withdrawal_penalty
+                } else {
+                    Decimal::zero()
                 };
 
-                resp = resp.add_message(bank_msg)
+                resp = refund(
+                    deps.storage,
+                    auction_id,
+                    &base_info,
+                    &info.sender,
+                    &receiver_addr,
+                    bid,
+                    penalty,
+                    resp,
+                )?;
             }
             None => {
                 return Err(ContractError::NoFundsToRetract {});
             }
         }
 
+        // The next best deposit takes over the runner-up's place
+        if runner_up_withdrawn {
+            let leader = HIGHEST_BID.load(deps.storage, auction_id)?;
+            match best_other_bid(deps.storage, auction_id, &leader.address)? {
+                Some(next) => RUNNER_UP.save(deps.storage, auction_id, &next)?,
+                None => RUNNER_UP.remove(deps.storage, auction_id),
+            }
+        }
+
         resp = resp
             .add_attribute("action", "retract")
-            .add_attribute("sender", receiver_addr);
+            .add_attribute("auction_id", auction_id.to_string())
+            .add_attribute("sender", receiver_addr.as_str());
 
         Ok(resp)
     }
+
+    pub fn recover_stray_funds(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        denom: String,
+        recipient: Option<String>,
+    ) -> Result<Response, ContractError> {
+        let admin = deps
+            .querier
+            .query_wasm_contract_info(&env.contract.address)?
+            .admin;
+        if admin.as_deref() != Some(info.sender.as_str()) {
+            return Err(ContractError::Unauthorized {
+                owner: admin.unwrap_or_default(),
+            });
+        }
+
+        let recipient = match recipient {
+            Some(recipient) => deps.api.addr_validate(&recipient)?,
+            None => info.sender,
+        };
+        let balance = deps
+            .querier
+            .query_balance(&env.contract.address, &denom)?
+            .amount;
+        let held = HELD.may_load(deps.storage, &denom)?.unwrap_or_default();
+        let amount = balance.saturating_sub(held);
+        if amount.is_zero() {
+            return Err(ContractError::NoStrayFunds { denom });
+        }
+
+        let recovered = Coin { denom, amount };
+        Ok(Response::new()
+            .add_message(BankMsg::Send {
+                to_address: recipient.to_string(),
+                amount: vec![recovered.clone()],
+            })
+            .add_attribute("action", "recover_stray_funds")
+            .add_attribute("recipient", recipient.as_str())
+            .add_attribute("amount", recovered.to_string()))
+    }
 }
 
 pub mod query {
-    use cosmwasm_std::{Addr, Coin, Deps, StdResult, Uint128};
+    use cosmwasm_std::{Addr, Coin, Deps, Env, Order, StdError, StdResult, Uint128};
+    use cw_storage_plus::Bound;
 
-    use crate::msg::{HighestBidResp, InfoResp};
-    use crate::state::{BASE_INFO, BIDS, HIGHEST_BID};
-
-    pub fn info(deps: Deps) -> StdResult<InfoResp> {
-        let base_info = BASE_INFO.load(deps.storage)?;
+    use crate::msg::{
+        AcceptedDenom, AllBidsResp, AllocationResp, BidHistoryResp, BidResp, BidsResp,
+        DenomSolvency, FeeRecipient, HighestBidResp, InfoResp, ListAuctionsResp, Outcome,
+        OwnershipResp, Rate, RunnerUpResp, SettlementResp, SolvencyResp, SortOrder, WinnerResp,
+    };
+    use crate::state::{
+        self, BaseInfo, RateSource, ALLOCATIONS, AUCTIONS, BID_HISTORY, CLEARING_PRICE,
+        HIGHEST_BID, PENDING_OWNER, RUNNER_UP, SALES,
+    };
 
-        Ok(InfoResp {
+    fn info_resp(auction_id: u64, base_info: BaseInfo) -> InfoResp {
+        InfoResp {
+            auction_id,
             owner: base_info.owner,
             commodity: base_info.commodity,
+            lot: base_info.lot,
             bidding_denom: base_info.bidding_denom,
+            bidding_asset: base_info.bidding_asset,
             commission: base_info.commission,
+            start: base_info.start,
+            end: base_info.end,
+            anti_sniping: base_info.anti_sniping,
+            effective_end: base_info.effective_end,
+            reserve: base_info.reserve,
+            starting_price: base_info.starting_price,
+            min_increment: base_info.min_increment,
+            mode: base_info.mode,
+            settlement: base_info.settlement,
+            buy_now_price: base_info.buy_now_price,
+            refund_on_outbid: base_info.refund_on_outbid,
+            withdrawal_penalty: base_info.withdrawal_penalty,
+            fee_recipients: base_info
+                .fee_recipients
+                .into_iter()
+                .map(|share| FeeRecipient {
+                    address: share.address.into_string(),
+                    weight: share.weight,
+                })
+                .collect(),
+            commission_mode: base_info.commission_mode,
+            accepted_denoms: base_info
+                .accepted_denoms
+                .into_iter()
+                .map(|accepted| AcceptedDenom {
+                    denom: accepted.denom,
+                    rate: match accepted.rate {
+                        RateSource::Fixed(rate) => Rate::Fixed(rate),
+                        RateSource::Oracle(contract) => Rate::Oracle {
+                            contract: contract.into_string(),
+                        },
+                    },
+                })
+                .collect(),
+            stray_funds: base_info.stray_funds,
             active: base_info.active,
-        })
+        }
+    }
+
+    pub fn info(deps: Deps, auction_id: u64) -> StdResult<InfoResp> {
+        let base_info = AUCTIONS.load(deps.storage, auction_id)?;
+
+        Ok(info_resp(auction_id, base_info))
+    }
+
+    pub fn list_auctions(
+        deps: Deps,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+        active: Option<bool>,
+        owner: Option<String>,
+    ) -> StdResult<ListAuctionsResp> {
+        let limit = limit.unwrap_or(super::DEFAULT_LIMIT).min(super::MAX_LIMIT) as usize;
+        let start = start_after.map(Bound::exclusive);
+        let owner = owner
+            .map(|owner| deps.api.addr_validate(&owner))
+            .transpose()?;
+
+        let auctions = AUCTIONS
+            .range(deps.storage, start, None, Order::Ascending)
+            .filter(|auction| match auction {
+                Ok((_, base_info)) => {
+                    active.is_none_or(|active| base_info.active == active)
+                        && owner.as_ref().is_none_or(|owner| base_info.owner == *owner)
+                }
+                Err(_) => true,
+            })
+            .take(limit)
+            .map(|auction| auction.map(|(auction_id, base_info)| info_resp(auction_id, base_info)))
+            .collect::<StdResult<_>>()?;
+
+        Ok(ListAuctionsResp { auctions })
     }
 
-    pub fn bids(deps: Deps, address: String) -> StdResult<Coin> {
+    pub fn bids(deps: Deps, auction_id: u64, address: String) -> StdResult<BidsResp> {
         let addr = Addr::unchecked(address); // Ignoring to check address format as it's not critical for the contract
-        let bid = BIDS.may_load(deps.storage, addr)?;
+        let base_info = AUCTIONS.load(deps.storage, auction_id)?;
+        let bid = state::bids().may_load(deps.storage, (auction_id, addr.clone()))?;
 
         if let Some(bid) = bid {
-            return Ok(bid);
+            return Ok(BidsResp {
+                deposits: super::held_coins(
+                    deps.storage,
+                    auction_id,
+                    &base_info,
+                    &addr,
+                    bid.clone(),
+                )?,
+                bid,
+            });
         }
 
-        let base_info = BASE_INFO.load(deps.storage)?;
-
-        Ok(Coin {
-            denom: base_info.bidding_denom,
-            amount: Uint128::zero(),
+        Ok(BidsResp {
+            bid: Coin {
+                denom: base_info.bidding_denom,
+                amount: Uint128::zero(),
+            },
+            deposits: vec![],
         })
     }
 
-    pub fn highest_bid(deps: Deps) -> StdResult<HighestBidResp> {
-        let highest_bid = HIGHEST_BID.may_load(deps.storage)?;
+    pub fn all_bids(
+        deps: Deps,
+        auction_id: u64,
+        start_after: Option<String>,
+        limit: Option<u32>,
+        order: Option<SortOrder>,
+    ) -> StdResult<AllBidsResp> {
+        let limit = limit.unwrap_or(super::DEFAULT_LIMIT).min(super::MAX_LIMIT) as usize;
+        let order = Order::from(order.unwrap_or_default());
+        let bound = start_after.map(|address| Bound::exclusive(Addr::unchecked(address)));
+        let (min, max) = match order {
+            Order::Ascending => (bound, None),
+            Order::Descending => (None, bound),
+        };
+
+        let bids = state::bids()
+            .prefix(auction_id)
+            .range(deps.storage, min, max, order)
+            .take(limit)
+            .map(|bid| {
+                bid.map(|(address, bid)| BidResp {
+                    address: address.to_string(),
+                    bid,
+                })
+            })
+            .collect::<StdResult<_>>()?;
+
+        Ok(AllBidsResp { bids })
+    }
+
+    /// Bidders with the largest deposits first, read from the amount index.
+    pub fn top_bids(deps: Deps, auction_id: u64, limit: Option<u32>) -> StdResult<AllBidsResp> {
+        let limit = limit.unwrap_or(super::DEFAULT_LIMIT).min(super::MAX_LIMIT) as usize;
+
+        let bids = state::bids()
+            .idx
+            .amount
+            .sub_prefix(auction_id)
+            .range(deps.storage, None, None, Order::Descending)
+            .take(limit)
+            .map(|bid| {
+                bid.map(|((_, address), bid)| BidResp {
+                    address: address.to_string(),
+                    bid,
+                })
+            })
+            .collect::<StdResult<_>>()?;
+
+        Ok(AllBidsResp { bids })
+    }
+
+    pub fn bid_history(
+        deps: Deps,
+        auction_id: u64,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<BidHistoryResp> {
+        let limit = limit.unwrap_or(super::DEFAULT_LIMIT).min(super::MAX_LIMIT) as usize;
+        let start = start_after.map(Bound::exclusive);
+
+        let bids = BID_HISTORY
+            .prefix(auction_id)
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|entry| entry.map(|(_, entry)| entry))
+            .collect::<StdResult<_>>()?;
+
+        Ok(BidHistoryResp { bids })
+    }
+
+    pub fn highest_bid(deps: Deps, auction_id: u64) -> StdResult<HighestBidResp> {
+        let highest_bid = HIGHEST_BID.may_load(deps.storage, auction_id)?;
 
         if let Some(highest_bid) = highest_bid {
             return Ok(HighestBidResp {
@@ -246,7 +2501,7 @@ pub mod query {
             });
         }
 
-        let base_info = BASE_INFO.load(deps.storage)?;
+        let base_info = AUCTIONS.load(deps.storage, auction_id)?;
 
         Ok(HighestBidResp {
             address: "".to_string(),
@@ -257,13 +2512,86 @@ pub mod query {
         })
     }
 
-    pub fn winner(deps: Deps) -> StdResult<HighestBidResp> {
-        let base_info = BASE_INFO.load(deps.storage)?;
-        if !base_info.active {
-            return highest_bid(deps);
+    pub fn min_next_bid(deps: Deps, auction_id: u64) -> StdResult<Coin> {
+        let base_info = AUCTIONS.load(deps.storage, auction_id)?;
+
+        Ok(Coin {
+            amount: super::min_next_bid(deps.storage, auction_id, &base_info)?,
+            denom: base_info.bidding_denom,
+        })
+    }
+
+    pub fn allocation(deps: Deps, auction_id: u64, address: String) -> StdResult<AllocationResp> {
+        let addr = deps.api.addr_validate(&address)?;
+        let base_info = AUCTIONS.load(deps.storage, auction_id)?;
+
+        Ok(AllocationResp {
+            quantity: ALLOCATIONS
+                .may_load(deps.storage, (auction_id, addr))?
+                .unwrap_or_default(),
+            price: Coin {
+                amount: CLEARING_PRICE
+                    .may_load(deps.storage, auction_id)?
+                    .unwrap_or_default(),
+                denom: base_info.bidding_denom,
+            },
+        })
+    }
+
+    pub fn ownership(deps: Deps, auction_id: u64) -> StdResult<OwnershipResp> {
+        let base_info = AUCTIONS.load(deps.storage, auction_id)?;
+        let pending = PENDING_OWNER.may_load(deps.storage, auction_id)?;
+
+        Ok(OwnershipResp {
+            owner: (!base_info.renounced).then_some(base_info.owner),
+            pending_owner: pending.as_ref().map(|pending| pending.address.clone()),
+            pending_expiry: pending.and_then(|pending| pending.expiry),
+        })
+    }
+
+    pub fn current_price(deps: Deps, env: Env, auction_id: u64) -> StdResult<Coin> {
+        let base_info = AUCTIONS.load(deps.storage, auction_id)?;
+        let price = super::dutch_price(&base_info, &env.block)
+            .ok_or_else(|| StdError::generic_err("Not a Dutch auction"))?;
+
+        Ok(Coin {
+            amount: price,
+            denom: base_info.bidding_denom,
+        })
+    }
+
+    pub fn winner(deps: Deps, auction_id: u64) -> StdResult<WinnerResp> {
+        let base_info = AUCTIONS.load(deps.storage, auction_id)?;
+        if base_info.outcome == Outcome::Sold {
+            let winner = highest_bid(deps, auction_id)?;
+            return Ok(WinnerResp {
+                outcome: base_info.outcome,
+                address: winner.address,
+                bid: winner.bid,
+            });
         }
 
-        Ok(HighestBidResp {
+        Ok(WinnerResp {
+            outcome: base_info.outcome,
+            address: "".to_string(),
+            bid: Coin {
+                denom: base_info.bidding_denom,
+                amount: Uint128::zero(),
+            },
+        })
+    }
+
+    pub fn runner_up(deps: Deps, auction_id: u64) -> StdResult<RunnerUpResp> {
+        if let Some(runner_up) = RUNNER_UP.may_load(deps.storage, auction_id)? {
+            return Ok(RunnerUpResp {
+                address: runner_up.address.to_string(),
+                bid: runner_up.bid,
+            });
+        }
+
+        let base_info = AUCTIONS.load(deps.storage, auction_id)?;
+
+        Ok(RunnerUpResp {
             address: "".to_string(),
             bid: Coin {
                 denom: base_info.bidding_denom,
@@ -271,4 +2599,71 @@ pub mod query {
             },
         })
     }
+
+    pub fn settlement(deps: Deps, auction_id: u64) -> StdResult<SettlementResp> {
+        let base_info = AUCTIONS.load(deps.storage, auction_id)?;
+        let Some(sale) = SALES.may_load(deps.storage, auction_id)? else {
+            return Ok(SettlementResp {
+                outcome: base_info.outcome,
+                buyer: None,
+                price: vec![],
+                commission: vec![],
+                royalty_recipient: None,
+                royalty: vec![],
+                seller_proceeds: vec![],
+            });
+        };
+
+        Ok(SettlementResp {
+            outcome: base_info.outcome,
+            buyer: Some(sale.buyer),
+            price: sale.price,
+            commission: sale.commission,
+            royalty_recipient: sale
+                .royalties
+                .first()
+                .map(|royalty| royalty.recipient.clone()),
+            royalty: sale
+                .royalties
+                .into_iter()
+                .map(|royalty| royalty.amount)
+                .collect(),
+            seller_proceeds: sale.seller_proceeds,
+        })
+    }
+
+    /// Every denom the contract holds or owes bidders. Obligations are totalled from the
+    /// deposits themselves rather than the running `HELD` count, so they check it too.
+    pub fn solvency(deps: Deps, env: Env) -> StdResult<SolvencyResp> {
+        let held = super::owed_to_bidders(deps.storage)?;
+        let mut balances = deps.querier.query_all_balances(&env.contract.address)?;
+        for coin in &held {
+            if !balances.iter().any(|balance| balance.denom == coin.denom) {
+                balances.push(Coin::new(0, &coin.denom));
+            }
+        }
+        balances.sort_by(|a, b| a.denom.cmp(&b.denom));
+
+        let denoms: Vec<_> = balances
+            .into_iter()
+            .map(|balance| {
+                let obligations = held
+                    .iter()
+                    .find(|held| held.denom == balance.denom)
+                    .map_or(Uint128::zero(), |held| held.amount);
+                DenomSolvency {
+                    surplus: balance.amount.saturating_sub(obligations),
+                    deficit: obligations.saturating_sub(balance.amount),
+                    denom: balance.denom,
+                    balance: balance.amount,
+                    obligations,
+                }
+            })
+            .collect();
+
+        Ok(SolvencyResp {
+            solvent: denoms.iter().all(|denom| denom.deficit.is_zero()),
+            denoms,
+        })
+    }
 }
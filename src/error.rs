@@ -12,18 +12,106 @@ pub enum ContractError {
     #[error("Auction is closed.")]
     AuctionClosed {},
 
+    #[error("Auction hasn't started yet.")]
+    AuctionNotStarted {},
+
+    #[error("Auction has ended.")]
+    AuctionEnded {},
+
+    #[error("Invalid schedule - end must be after start and both in the same unit.")]
+    InvalidSchedule {},
+
+    #[error("Anti-sniping requires an end and durations in the same unit as it.")]
+    InvalidAntiSniping {},
+
+    #[error("Revealed reserve doesn't match its commitment.")]
+    InvalidReserveReveal {},
+
+    #[error("Hidden reserve has to be revealed to close the auction.")]
+    ReserveNotRevealed {},
+
+    #[error("Not supported in this auction mode.")]
+    WrongAuctionMode {},
+
+    #[error("Reveal phase isn't open.")]
+    NotRevealPhase {},
+
+    #[error("Reveal phase isn't over yet.")]
+    RevealPhaseNotOver {},
+
+    #[error("Revealed bid doesn't match its commitment or exceeds the deposit.")]
+    InvalidBidReveal {},
+
+    #[error("Penalty can't exceed 100%.")]
+    InvalidPenalty {},
+
+    #[error("Commission can't exceed 100%.")]
+    InvalidCommission {},
+
+    #[error("Dutch auction start price can't be below the floor price.")]
+    InvalidDutchPrice {},
+
+    #[error("Quantity must be positive and at most the units on sale.")]
+    InvalidQuantity {},
+
+    #[error("Funds sent must be exactly {expected}.")]
+    IncorrectDeposit { expected: String },
+
+    #[error("Buy-now price can't be below the starting price or the reserve.")]
+    InvalidBuyNowPrice {},
+
+    #[error("Leading bid can't be withdrawn before close.")]
+    LeadingBidLocked {},
+
+    #[error("Can't migrate from contract {contract}.")]
+    WrongContract { contract: String },
+
+    #[error("Can't migrate from version {stored} down to {current}.")]
+    Downgrade { stored: String, current: String },
+
+    #[error(
+        "Contract holds {balance} but legacy bids still claim {owed}, they can't all be paid."
+    )]
+    UnfundedLegacyBids { owed: String, balance: String },
+
+    #[error("No ownership transfer is pending for the sender.")]
+    NoOwnershipTransfer {},
+
+    #[error("Ownership transfer has expired.")]
+    OwnershipTransferExpired {},
+
+    #[error("Ownership of the auction has been renounced.")]
+    OwnershipRenounced {},
+
+    #[error("Ownership can only be renounced on auctions with an end.")]
+    RenounceWithoutEnd {},
+
+    #[error("Fee recipients need positive weights.")]
+    InvalidFeeRecipients {},
+
+    #[error(
+        "Accepted denoms must differ from each other and the bidding denom, with positive rates."
+    )]
+    InvalidAcceptedDenoms {},
+
+    #[error("Funds in {denom} aren't used by this message.")]
+    StrayFunds { denom: String },
+
+    #[error("No {denom} to recover beyond what bidders deposited.")]
+    NoStrayFunds { denom: String },
+
     #[error("Auction isn't closed yet.")]
     AuctionNotClosed {},
 
     #[error("Owner can not bid.")]
-    BiddingByOwner { },
+    BiddingByOwner {},
 
     #[error("Bid with wrong coin. Must be in {denom}.")]
     InvalidDenom { denom: String },
 
-    #[error("Bid is too low, must be higher than {highest_bid}.")]
-    BidTooLow { highest_bid: String },
+    #[error("Bid is too low, total bid after commission must be at least {min_bid}.")]
+    BidBelowMinimum { min_bid: String },
 
     #[error("No funds to retract.")]
     NoFundsToRetract {},
-}
\ No newline at end of file
+}
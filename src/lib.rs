@@ -3,8 +3,9 @@ use cosmwasm_std::entry_point;
 
 use cosmwasm_std::{to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult};
 use error::ContractError;
-use msg::InstantiateMsg;
+use msg::{InstantiateMsg, MigrateMsg};
 
+pub mod commitment;
 mod contract;
 pub mod error;
 pub mod msg;
@@ -16,16 +17,21 @@ mod state;
 pub fn instantiate(
     deps: DepsMut,
     _env: Env,
-    info: MessageInfo,
+    _info: MessageInfo,
     msg: InstantiateMsg,
-) -> StdResult<Response> {
-    contract::instantiate(deps, info, msg.owner, msg.commodity, msg.bidding_denom, msg.commission)
+) -> Result<Response, ContractError> {
+    contract::instantiate(deps, msg)
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    contract::migrate(deps, env, msg)
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     msg: msg::ExecMsg,
 ) -> Result<Response, ContractError> {
@@ -33,21 +39,101 @@ pub fn execute(
     use msg::ExecMsg::*;
 
     match msg {
-        Bid {} => exec::bid(deps, info),
-        Close {} => exec::close(deps, info),
-        Retract { receiver } => exec::retract(deps, info, receiver),
+        CreateAuction(msg) => exec::create_auction(deps, env, info, msg),
+        ReceiveNft(msg) => exec::receive_nft(deps, env, info, msg),
+        Receive(msg) => exec::receive_cw20(deps, env, info, msg),
+        Bid { auction_id } => exec::bid(deps, env, info, auction_id),
+        BidUnits {
+            auction_id,
+            quantity,
+            price,
+        } => exec::bid_units(deps, env, info, auction_id, quantity, price),
+        CommitBid {
+            auction_id,
+            commitment,
+        } => exec::commit_bid(deps, env, info, auction_id, commitment),
+        RevealBid {
+            auction_id,
+            amount,
+            salt,
+        } => exec::reveal_bid(deps, env, info, auction_id, amount, salt),
+        Close {
+            auction_id,
+            reserve,
+        } => exec::close(deps, env, info, auction_id, reserve),
+        Retract {
+            auction_id,
+            receiver,
+        } => exec::retract(deps, info, auction_id, receiver),
+        ProposeNewOwner {
+            auction_id,
+            new_owner,
+            expiry,
+        } => exec::propose_new_owner(deps, env, info, auction_id, new_owner, expiry),
+        AcceptOwnership { auction_id } => exec::accept_ownership(deps, env, info, auction_id),
+        CancelOwnershipTransfer { auction_id } => {
+            exec::cancel_ownership_transfer(deps, info, auction_id)
+        }
+        RenounceOwnership { auction_id } => exec::renounce_ownership(deps, info, auction_id),
+        RefundAll { auction_id, limit } => exec::refund_all(deps, info, auction_id, limit),
+        RecoverStrayFunds { denom, recipient } => {
+            exec::recover_stray_funds(deps, env, info, denom, recipient)
+        }
     }
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: msg::QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: msg::QueryMsg) -> StdResult<Binary> {
     use contract::query;
     use msg::QueryMsg::*;
 
     match msg {
-        Info {} => to_binary(&query::info(deps)?),
-        Bids { address } => to_binary(&query::bids(deps, address)?),
-        HighestBid {} => to_binary(&query::highest_bid(deps)?),
-        Winner {} => to_binary(&query::winner(deps)?),
+        Info { auction_id } => to_binary(&query::info(deps, auction_id)?),
+        ListAuctions {
+            start_after,
+            limit,
+            active,
+            owner,
+        } => to_binary(&query::list_auctions(
+            deps,
+            start_after,
+            limit,
+            active,
+            owner,
+        )?),
+        Bids {
+            auction_id,
+            address,
+        } => to_binary(&query::bids(deps, auction_id, address)?),
+        AllBids {
+            auction_id,
+            start_after,
+            limit,
+            order,
+        } => to_binary(&query::all_bids(
+            deps,
+            auction_id,
+            start_after,
+            limit,
+            order,
+        )?),
+        TopBids { auction_id, limit } => to_binary(&query::top_bids(deps, auction_id, limit)?),
+        BidHistory {
+            auction_id,
+            start_after,
+            limit,
+        } => to_binary(&query::bid_history(deps, auction_id, start_after, limit)?),
+        HighestBid { auction_id } => to_binary(&query::highest_bid(deps, auction_id)?),
+        Winner { auction_id } => to_binary(&query::winner(deps, auction_id)?),
+        RunnerUp { auction_id } => to_binary(&query::runner_up(deps, auction_id)?),
+        MinNextBid { auction_id } => to_binary(&query::min_next_bid(deps, auction_id)?),
+        Ownership { auction_id } => to_binary(&query::ownership(deps, auction_id)?),
+        Allocation {
+            auction_id,
+            address,
+        } => to_binary(&query::allocation(deps, auction_id, address)?),
+        CurrentPrice { auction_id } => to_binary(&query::current_price(deps, env, auction_id)?),
+        Settlement { auction_id } => to_binary(&query::settlement(deps, auction_id)?),
+        Solvency {} => to_binary(&query::solvency(deps, env)?),
     }
-}
\ No newline at end of file
+}
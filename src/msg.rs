@@ -1,45 +1,540 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::{Coin, Decimal, Addr};
+use cosmwasm_std::{Addr, Coin, Decimal, HexBinary, Order, Timestamp, Uint128};
+use cw20::Cw20ReceiveMsg;
+use cw721::Cw721ReceiveMsg;
+use cw_utils::{Duration, Scheduled};
 
 #[cw_serde]
 pub struct InstantiateMsg {
+    /// Default commission split for new auctions, the owner gets it all if not set.
+    pub fee_recipients: Option<Vec<FeeRecipient>>,
+}
+
+/// When commission is charged.
+#[cw_serde]
+#[derive(Default)]
+pub enum CommissionMode {
+    /// From every bid as it's placed, kept even if the bid loses.
+    #[default]
+    PerBid,
+    /// Only from the price the winner pays, losers get their whole deposit back.
+    OnSettlement,
+}
+
+/// Gets `weight` parts of the commission.
+#[cw_serde]
+pub struct FeeRecipient {
+    pub address: String,
+    pub weight: u32,
+}
+
+#[cw_serde]
+pub struct MigrateMsg {}
+
+#[cw_serde]
+pub struct CreateAuctionMsg {
+    /// Defaults to the sender.
     pub owner: Option<String>,
     pub commodity: String,
+    /// Native denom, or the CW20 contract address if `bidding_asset` is `Cw20`.
     pub bidding_denom: String,
+    /// Defaults to `Native`.
+    pub bidding_asset: Option<AssetKind>,
     pub commission: Decimal,
+    /// Bids are accepted only once this height or time is reached.
+    pub start: Option<Scheduled>,
+    /// Bids are rejected from this height or time on, and anyone can close the auction.
+    pub end: Option<Scheduled>,
+    /// Pushes the end back when a bid lands close to it. Requires `end`.
+    pub anti_sniping: Option<AntiSniping>,
+    /// Minimum the winner's deposit must reach for the lot to be sold.
+    pub reserve: Option<Reserve>,
+    /// Minimum total bid after commission for the first bid.
+    pub starting_price: Option<Uint128>,
+    /// How much every bid has to raise the leading one by.
+    pub min_increment: Option<MinIncrement>,
+    /// Defaults to `English`.
+    pub mode: Option<AuctionMode>,
+    /// Defaults to `FirstPrice`.
+    pub settlement: Option<Settlement>,
+    /// A bid bringing the total after commission to this price wins right away. English
+    /// first-price auctions only.
+    pub buy_now_price: Option<Uint128>,
+    /// Pays an outbid leader back right away instead of waiting for a retract after close.
+    /// English auctions only, defaults to `false`.
+    pub refund_on_outbid: Option<bool>,
+    /// Share of the deposit paid to the owner by a bidder withdrawing before close. English
+    /// auctions only, the others don't allow withdrawing.
+    pub withdrawal_penalty: Option<Decimal>,
+    /// Overrides the contract default commission split, empty pays it all to the owner.
+    pub fee_recipients: Option<Vec<FeeRecipient>>,
+    /// Defaults to `PerBid`. Multi-unit auctions always charge at settlement.
+    pub commission_mode: Option<CommissionMode>,
+    /// Native denoms accepted besides `bidding_denom`. English first-price auctions without
+    /// a buy-now price only.
+    pub accepted_denoms: Option<Vec<AcceptedDenom>>,
+    /// Defaults to `Reject`. Applies to every message on the auction, creating it included.
+    pub stray_funds: Option<StrayFunds>,
+}
+
+/// What happens to coins sent with a message that it doesn't use.
+#[cw_serde]
+#[derive(Default)]
+pub enum StrayFunds {
+    /// The message fails.
+    #[default]
+    Reject,
+    /// They are sent back to the sender in the same response.
+    Refund,
+}
+
+/// Bids in `denom` are valued at `rate` units of the bidding denom per unit, as of the block
+/// they are placed in.
+#[cw_serde]
+pub struct AcceptedDenom {
+    pub denom: String,
+    pub rate: Rate,
+}
+
+#[cw_serde]
+pub enum Rate {
+    Fixed(Decimal),
+    /// Read from a price oracle contract, see `OracleQueryMsg`.
+    Oracle {
+        contract: String,
+    },
+}
+
+/// What the winner pays on close.
+#[cw_serde]
+#[derive(Default)]
+pub enum Settlement {
+    /// The winning bid.
+    #[default]
+    FirstPrice,
+    /// The runner-up bid, but at least the starting price and the reserve. The rest of the
+    /// winning bid is refunded.
+    SecondPrice,
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub enum AuctionMode {
+    /// Open ascending bids.
+    #[default]
+    English,
+    /// Bids are committed until `end` and revealed until `reveal_end`. Commission is charged
+    /// on the revealed amount, `unrevealed_penalty` is the share of the deposit an unrevealed
+    /// commitment forfeits to the owner.
+    Sealed {
+        reveal_end: Scheduled,
+        unrevealed_penalty: Decimal,
+    },
+    /// Price falls by `decrement` every `step` from `start` (the creation block if not set)
+    /// until it reaches `floor_price`. The first bid sending at least the current price wins
+    /// and closes the auction.
+    Dutch {
+        start_price: Uint128,
+        floor_price: Uint128,
+        decrement: Uint128,
+        step: Duration,
+    },
+    /// Sells `quantity` identical units. Bids name a quantity and a per-unit price, and on
+    /// close the best ones get their units at the lowest accepted price.
+    MultiUnit { quantity: u64 },
+}
+
+/// Bids placed within `window` before the end extend it by `extension`.
+/// Both durations must be in the same unit as the auction end.
+#[cw_serde]
+pub struct AntiSniping {
+    pub window: Duration,
+    pub extension: Duration,
+}
+
+#[cw_serde]
+pub enum Reserve {
+    Public {
+        amount: Uint128,
+    },
+    /// Hidden until close, see `commitment::reserve_commitment`.
+    Hidden {
+        commitment: HexBinary,
+    },
+}
+
+#[cw_serde]
+pub struct ReserveReveal {
+    pub amount: Uint128,
+    pub salt: String,
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub enum AssetKind {
+    #[default]
+    Native,
+    /// Bids arrive through `Cw20ReceiveMsg` hooks.
+    Cw20,
+}
+
+/// On-chain asset escrowed by the contract and delivered on close.
+#[cw_serde]
+pub enum Lot {
+    Cw721 { contract: Addr, token_id: String },
+    Cw20 { contract: Addr, amount: Uint128 },
+}
+
+/// CW2981 royalty query sent to the contract of an NFT lot.
+#[cw_serde]
+pub enum Cw2981QueryMsg {
+    Extension { msg: RoyaltyQueryMsg },
+}
+
+#[cw_serde]
+pub enum RoyaltyQueryMsg {
+    RoyaltyInfo {
+        token_id: String,
+        sale_price: Uint128,
+    },
+}
+
+#[cw_serde]
+pub struct RoyaltiesInfoResponse {
+    pub address: String,
+    pub royalty_amount: Uint128,
+}
+
+/// Query sent to a price oracle for the value of one unit of `base` in `quote`.
+#[cw_serde]
+pub enum OracleQueryMsg {
+    ExchangeRate { base: String, quote: String },
+}
+
+#[cw_serde]
+pub struct ExchangeRateResponse {
+    pub rate: Decimal,
+}
+
+/// Message expected inside `Cw721ReceiveMsg` when an NFT is sent to the contract.
+#[cw_serde]
+pub enum ReceiveNftMsg {
+    /// Starts an auction of the received token.
+    CreateAuction(CreateAuctionMsg),
+}
+
+/// Message expected inside `Cw20ReceiveMsg` when tokens are sent to the contract.
+#[cw_serde]
+#[allow(clippy::large_enum_variant)]
+pub enum ReceiveMsg {
+    /// Bids the received tokens.
+    Bid { auction_id: u64 },
+    /// Bids the received tokens for units of a multi-unit auction.
+    BidUnits {
+        auction_id: u64,
+        quantity: u64,
+        price: Uint128,
+    },
+    /// Deposits the received tokens for a sealed bid.
+    CommitBid {
+        auction_id: u64,
+        commitment: HexBinary,
+    },
+    /// Starts an auction of the received tokens.
+    CreateAuction(CreateAuctionMsg),
+}
+
+#[cw_serde]
+pub enum MinIncrement {
+    Amount(Uint128),
+    /// Fraction of the leading bid, `0.05` meaning 5%.
+    Percent(Decimal),
 }
 
 #[cw_serde]
 #[derive(QueryResponses)]
 pub enum QueryMsg {
     #[returns(InfoResp)]
-    Info {},
-    #[returns(Coin)]
-    Bids {address: String},
-    #[returns(HighestBidResp)]
-    HighestBid {},
+    Info { auction_id: u64 },
+    #[returns(ListAuctionsResp)]
+    ListAuctions {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+        active: Option<bool>,
+        owner: Option<String>,
+    },
+    #[returns(BidsResp)]
+    Bids { auction_id: u64, address: String },
+    /// Deposits of all bidders by address, ascending unless `order` says otherwise.
+    #[returns(AllBidsResp)]
+    AllBids {
+        auction_id: u64,
+        start_after: Option<String>,
+        limit: Option<u32>,
+        order: Option<SortOrder>,
+    },
+    /// Bidders ordered by their deposit, largest first.
+    #[returns(AllBidsResp)]
+    TopBids { auction_id: u64, limit: Option<u32> },
+    /// Bids in the order they were placed. Sealed bids show up once revealed.
+    #[returns(BidHistoryResp)]
+    BidHistory {
+        auction_id: u64,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
     #[returns(HighestBidResp)]
-    Winner {},
+    HighestBid { auction_id: u64 },
+    #[returns(WinnerResp)]
+    Winner { auction_id: u64 },
+    /// Second best bidder, for sealed auctions known only after close.
+    #[returns(RunnerUpResp)]
+    RunnerUp { auction_id: u64 },
+    /// Total bid after commission needed to take the lead, in the bidding denom.
+    #[returns(Coin)]
+    MinNextBid { auction_id: u64 },
+    #[returns(OwnershipResp)]
+    Ownership { auction_id: u64 },
+    /// Units won in a closed multi-unit auction.
+    #[returns(AllocationResp)]
+    Allocation { auction_id: u64, address: String },
+    /// Price a Dutch auction can be won at in this block.
+    #[returns(Coin)]
+    CurrentPrice { auction_id: u64 },
+    /// How the winning price of a closed auction was paid out.
+    #[returns(SettlementResp)]
+    Settlement { auction_id: u64 },
+    /// Native balances of the contract against what it owes bidders of all auctions.
+    #[returns(SolvencyResp)]
+    Solvency {},
 }
 
 #[cw_serde]
+#[allow(clippy::large_enum_variant)]
 pub enum ExecMsg {
-    Bid {},
-    Close {},
-    Retract {receiver: Option<String>},
+    CreateAuction(CreateAuctionMsg),
+    ReceiveNft(Cw721ReceiveMsg),
+    Receive(Cw20ReceiveMsg),
+    Bid {
+        auction_id: u64,
+    },
+    /// Bids for `quantity` units at up to `price` each, funds sent must cover all of them.
+    BidUnits {
+        auction_id: u64,
+        quantity: u64,
+        price: Uint128,
+    },
+    /// Commits a sealed bid, see `commitment::bid_commitment`. Funds sent are the deposit
+    /// the revealed amount has to fit in.
+    CommitBid {
+        auction_id: u64,
+        commitment: HexBinary,
+    },
+    /// Reveals a sealed bid, the deposit above `amount` is refunded.
+    RevealBid {
+        auction_id: u64,
+        amount: Uint128,
+        salt: String,
+    },
+    /// A hidden reserve is considered not met unless revealed here. Until about a day after
+    /// the end only the owner can close it without revealing.
+    Close {
+        auction_id: u64,
+        reserve: Option<ReserveReveal>,
+    },
+    /// Pays back the sender's deposit once the auction is closed. Bidders not leading an
+    /// English auction can also withdraw before close, paying the withdrawal penalty.
+    Retract {
+        auction_id: u64,
+        receiver: Option<String>,
+    },
+    /// Offers control of the auction to `new_owner` until `expiry`. Replaces a pending offer.
+    ProposeNewOwner {
+        auction_id: u64,
+        new_owner: String,
+        expiry: Option<Scheduled>,
+    },
+    AcceptOwnership {
+        auction_id: u64,
+    },
+    CancelOwnershipTransfer {
+        auction_id: u64,
+    },
+    /// Gives up control for good. Proceeds still go to the owner.
+    RenounceOwnership {
+        auction_id: u64,
+    },
+    /// Refunds up to `limit` losing bidders of a closed auction. Can be called by anyone
+    /// until all are paid.
+    RefundAll {
+        auction_id: u64,
+        limit: Option<u32>,
+    },
+    /// Sends the part of the contract's `denom` balance no bidder has a claim on to
+    /// `recipient`, the sender by default. Contract admin only.
+    RecoverStrayFunds {
+        denom: String,
+        recipient: Option<String>,
+    },
 }
 
 #[cw_serde]
 pub struct InfoResp {
+    pub auction_id: u64,
     pub owner: Addr,
     pub commodity: String,
+    pub lot: Option<Lot>,
     pub bidding_denom: String,
+    pub bidding_asset: AssetKind,
     pub commission: Decimal,
+    pub start: Option<Scheduled>,
+    pub end: Option<Scheduled>,
+    pub anti_sniping: Option<AntiSniping>,
+    pub effective_end: Option<Scheduled>,
+    pub reserve: Option<Reserve>,
+    pub starting_price: Uint128,
+    pub min_increment: Option<MinIncrement>,
+    pub mode: AuctionMode,
+    pub settlement: Settlement,
+    pub buy_now_price: Option<Uint128>,
+    pub refund_on_outbid: bool,
+    pub withdrawal_penalty: Decimal,
+    pub fee_recipients: Vec<FeeRecipient>,
+    pub commission_mode: CommissionMode,
+    pub accepted_denoms: Vec<AcceptedDenom>,
+    pub stray_funds: StrayFunds,
     pub active: bool,
 }
 
+#[cw_serde]
+pub struct ListAuctionsResp {
+    pub auctions: Vec<InfoResp>,
+}
+
 #[cw_serde]
 pub struct HighestBidResp {
     pub address: String,
     pub bid: Coin,
-}
\ No newline at end of file
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub enum SortOrder {
+    #[default]
+    Ascending,
+    Descending,
+}
+
+impl From<SortOrder> for Order {
+    fn from(order: SortOrder) -> Self {
+        match order {
+            SortOrder::Ascending => Order::Ascending,
+            SortOrder::Descending => Order::Descending,
+        }
+    }
+}
+
+/// `bid` is the deposit after commission valued in the bidding denom, `deposits` the coins
+/// actually held for the bidder.
+#[cw_serde]
+pub struct BidsResp {
+    pub bid: Coin,
+    pub deposits: Vec<Coin>,
+}
+
+#[cw_serde]
+pub struct BidResp {
+    pub address: String,
+    pub bid: Coin,
+}
+
+#[cw_serde]
+pub struct AllBidsResp {
+    pub bids: Vec<BidResp>,
+}
+
+/// A single bid, `gross` being what the bidder sent for it and `net` what was added to
+/// their deposit after commission.
+#[cw_serde]
+pub struct BidHistoryEntry {
+    pub id: u64,
+    pub address: Addr,
+    pub gross: Coin,
+    pub net: Coin,
+    pub commission: Coin,
+    pub height: u64,
+    pub time: Timestamp,
+}
+
+#[cw_serde]
+pub struct BidHistoryResp {
+    pub bids: Vec<BidHistoryEntry>,
+}
+
+#[cw_serde]
+pub enum Outcome {
+    Pending,
+    Sold,
+    NoBids,
+    ReserveNotMet,
+}
+
+/// `bid` is the runner-up's total after commission.
+#[cw_serde]
+pub struct RunnerUpResp {
+    pub address: String,
+    pub bid: Coin,
+}
+
+/// `owner` is `None` once ownership is renounced.
+#[cw_serde]
+pub struct OwnershipResp {
+    pub owner: Option<Addr>,
+    pub pending_owner: Option<Addr>,
+    pub pending_expiry: Option<Scheduled>,
+}
+
+/// `price` is the clearing price paid for each unit.
+#[cw_serde]
+pub struct AllocationResp {
+    pub quantity: u64,
+    pub price: Coin,
+}
+
+/// `commission` is only what was taken from the price at close, commission charged per bid
+/// isn't included. Amounts are per denom paid and empty unless the lot was sold.
+#[cw_serde]
+pub struct SettlementResp {
+    pub outcome: Outcome,
+    pub buyer: Option<Addr>,
+    pub price: Vec<Coin>,
+    pub commission: Vec<Coin>,
+    pub royalty_recipient: Option<Addr>,
+    pub royalty: Vec<Coin>,
+    pub seller_proceeds: Vec<Coin>,
+}
+
+#[cw_serde]
+pub struct WinnerResp {
+    pub outcome: Outcome,
+    pub address: String,
+    pub bid: Coin,
+}
+
+#[cw_serde]
+pub struct SolvencyResp {
+    /// `false` if any denom is in deficit.
+    pub solvent: bool,
+    pub denoms: Vec<DenomSolvency>,
+}
+
+/// `obligations` are the deposits still held for bidders, `surplus` and `deficit` what the
+/// balance exceeds or falls short of them by.
+#[cw_serde]
+pub struct DenomSolvency {
+    pub denom: String,
+    pub balance: Uint128,
+    pub obligations: Uint128,
+    pub surplus: Uint128,
+    pub deficit: Uint128,
+}
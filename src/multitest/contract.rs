@@ -1,9 +1,17 @@
-use cosmwasm_std::{Addr, Coin, StdResult, Decimal};
-use cw_multi_test::{App, ContractWrapper, Executor};
+use cosmwasm_std::{to_binary, Addr, Coin, HexBinary, StdResult, Uint128};
+use cw20::Cw20ExecuteMsg;
+use cw721::Cw721ExecuteMsg;
+use cw_multi_test::{App, AppResponse, ContractWrapper, Executor};
+use cw_utils::Scheduled;
 
 use crate::error::ContractError;
-use crate::msg::{ExecMsg, InstantiateMsg, QueryMsg, InfoResp, HighestBidResp};
-use crate::{execute, instantiate, query};
+use crate::msg::{
+    AllBidsResp, AllocationResp, BidHistoryResp, BidsResp, CreateAuctionMsg, ExecMsg,
+    HighestBidResp, InfoResp, InstantiateMsg, ListAuctionsResp, MigrateMsg, OwnershipResp,
+    QueryMsg, ReceiveMsg, ReceiveNftMsg, ReserveReveal, RunnerUpResp, SettlementResp, SolvencyResp,
+    SortOrder, WinnerResp,
+};
+use crate::{execute, instantiate, migrate, query};
 
 pub struct AuctionContract(Addr);
 
@@ -13,47 +21,216 @@ impl AuctionContract {
     }
 
     pub fn store_code(app: &mut App) -> u64 {
-        let contract = ContractWrapper::new(execute, instantiate, query);
+        let contract = ContractWrapper::new(execute, instantiate, query).with_migrate(migrate);
         app.store_code(Box::new(contract))
     }
 
     #[track_caller]
-    pub fn instantiate<'a>(app: &mut App, code_id: u64, sender: &Addr, label: &str, admin: Option<String>, commodity: &str, bidding_denom: String, commission: Decimal) -> StdResult<AuctionContract> {
-        
-        app.instantiate_contract(
-            code_id,
+    pub fn instantiate(
+        app: &mut App,
+        code_id: u64,
+        sender: &Addr,
+        label: &str,
+        admin: Option<String>,
+        msg: &InstantiateMsg,
+    ) -> Result<AuctionContract, ContractError> {
+        app.instantiate_contract(code_id, sender.clone(), msg, &[], label, admin)
+            .map(AuctionContract)
+            .map_err(|err| err.downcast().unwrap())
+    }
+
+    #[track_caller]
+    pub fn migrate(&self, app: &mut App, sender: &Addr, code_id: u64) -> Result<(), ContractError> {
+        app.migrate_contract(sender.clone(), self.0.clone(), &MigrateMsg {}, code_id)
+            .map_err(|err| err.downcast::<ContractError>().unwrap())?;
+
+        Ok(())
+    }
+
+    /// Creates a new auction and returns its id.
+    pub fn create_auction(
+        &self,
+        app: &mut App,
+        sender: &Addr,
+        msg: CreateAuctionMsg,
+    ) -> Result<u64, ContractError> {
+        let resp = app
+            .execute_contract(
+                sender.clone(),
+                self.0.clone(),
+                &ExecMsg::CreateAuction(msg),
+                &[],
+            )
+            .map_err(|err| err.downcast::<ContractError>().unwrap())?;
+
+        Ok(Self::auction_id(&resp))
+    }
+
+    /// Sends the NFT to the contract to be auctioned and returns the auction id.
+    pub fn create_auction_with_nft(
+        &self,
+        app: &mut App,
+        sender: &Addr,
+        nft: &Addr,
+        token_id: &str,
+        msg: CreateAuctionMsg,
+    ) -> Result<u64, ContractError> {
+        let resp = app
+            .execute_contract(
+                sender.clone(),
+                nft.clone(),
+                &Cw721ExecuteMsg::SendNft {
+                    contract: self.0.to_string(),
+                    token_id: token_id.to_string(),
+                    msg: to_binary(&ReceiveNftMsg::CreateAuction(msg)).unwrap(),
+                },
+                &[],
+            )
+            .map_err(|err| err.downcast::<ContractError>().unwrap())?;
+
+        Ok(Self::auction_id(&resp))
+    }
+
+    /// Sends CW20 tokens to the contract to be auctioned and returns the auction id.
+    pub fn create_auction_with_cw20(
+        &self,
+        app: &mut App,
+        sender: &Addr,
+        token: &Addr,
+        amount: u128,
+        msg: CreateAuctionMsg,
+    ) -> Result<u64, ContractError> {
+        let resp = app
+            .execute_contract(
+                sender.clone(),
+                token.clone(),
+                &Cw20ExecuteMsg::Send {
+                    contract: self.0.to_string(),
+                    amount: Uint128::new(amount),
+                    msg: to_binary(&ReceiveMsg::CreateAuction(msg)).unwrap(),
+                },
+                &[],
+            )
+            .map_err(|err| err.downcast::<ContractError>().unwrap())?;
+
+        Ok(Self::auction_id(&resp))
+    }
+
+    fn auction_id(resp: &AppResponse) -> u64 {
+        resp.events
+            .iter()
+            .flat_map(|event| &event.attributes)
+            .find(|attr| attr.key == "auction_id")
+            .unwrap()
+            .value
+            .parse()
+            .unwrap()
+    }
+
+    pub fn bid(
+        &self,
+        app: &mut App,
+        auction_id: u64,
+        sender: &Addr,
+        amount: &[Coin],
+    ) -> Result<(), ContractError> {
+        app.execute_contract(
             sender.clone(),
-            &InstantiateMsg {
-                commodity: commodity.to_string(),
-                commission,
-                owner: admin.clone(),
-                bidding_denom,
+            self.0.clone(),
+            &ExecMsg::Bid { auction_id },
+            amount,
+        )
+        .map_err(|err| err.downcast::<ContractError>().unwrap())?;
+
+        Ok(())
+    }
+
+    pub fn bid_cw20(
+        &self,
+        app: &mut App,
+        auction_id: u64,
+        sender: &Addr,
+        token: &Addr,
+        amount: u128,
+    ) -> Result<(), ContractError> {
+        app.execute_contract(
+            sender.clone(),
+            token.clone(),
+            &Cw20ExecuteMsg::Send {
+                contract: self.0.to_string(),
+                amount: Uint128::new(amount),
+                msg: to_binary(&ReceiveMsg::Bid { auction_id }).unwrap(),
             },
             &[],
-            label,
-            admin,
         )
-        .map(AuctionContract)
-        .map_err(|err| err.downcast().unwrap())
+        .map_err(|err| err.downcast::<ContractError>().unwrap())?;
+
+        Ok(())
+    }
+
+    pub fn bid_units(
+        &self,
+        app: &mut App,
+        auction_id: u64,
+        sender: &Addr,
+        quantity: u64,
+        price: u128,
+        funds: &[Coin],
+    ) -> Result<(), ContractError> {
+        app.execute_contract(
+            sender.clone(),
+            self.0.clone(),
+            &ExecMsg::BidUnits {
+                auction_id,
+                quantity,
+                price: Uint128::new(price),
+            },
+            funds,
+        )
+        .map_err(|err| err.downcast::<ContractError>().unwrap())?;
+
+        Ok(())
     }
 
-    pub fn bid(&self, app: &mut App, sender: &Addr, amount: &[Coin]) -> Result<(), ContractError> {
+    /// Commits a sealed bid with `deposit` attached.
+    pub fn commit_bid(
+        &self,
+        app: &mut App,
+        auction_id: u64,
+        sender: &Addr,
+        commitment: HexBinary,
+        deposit: &[Coin],
+    ) -> Result<(), ContractError> {
         app.execute_contract(
             sender.clone(),
             self.0.clone(),
-            &ExecMsg::Bid {},
-            &amount,
+            &ExecMsg::CommitBid {
+                auction_id,
+                commitment,
+            },
+            deposit,
         )
         .map_err(|err| err.downcast::<ContractError>().unwrap())?;
 
         Ok(())
     }
 
-    pub fn close(&self, app: &mut App, sender: &Addr) -> Result<(), ContractError> {
+    pub fn reveal_bid(
+        &self,
+        app: &mut App,
+        auction_id: u64,
+        sender: &Addr,
+        amount: u128,
+        salt: &str,
+    ) -> Result<(), ContractError> {
         app.execute_contract(
             sender.clone(),
             self.0.clone(),
-            &ExecMsg::Close {},
+            &ExecMsg::RevealBid {
+                auction_id,
+                amount: Uint128::new(amount),
+                salt: salt.to_string(),
+            },
             &[],
         )
         .map_err(|err| err.downcast::<ContractError>().unwrap())?;
@@ -61,11 +238,58 @@ impl AuctionContract {
         Ok(())
     }
 
-    pub fn retract(&self, app: &mut App, sender: &Addr) -> Result<(), ContractError> {
+    pub fn close(
+        &self,
+        app: &mut App,
+        auction_id: u64,
+        sender: &Addr,
+    ) -> Result<(), ContractError> {
+        app.execute_contract(
+            sender.clone(),
+            self.0.clone(),
+            &ExecMsg::Close {
+                auction_id,
+                reserve: None,
+            },
+            &[],
+        )
+        .map_err(|err| err.downcast::<ContractError>().unwrap())?;
+
+        Ok(())
+    }
+
+    pub fn close_with_reserve(
+        &self,
+        app: &mut App,
+        auction_id: u64,
+        sender: &Addr,
+        reserve: ReserveReveal,
+    ) -> Result<(), ContractError> {
+        app.execute_contract(
+            sender.clone(),
+            self.0.clone(),
+            &ExecMsg::Close {
+                auction_id,
+                reserve: Some(reserve),
+            },
+            &[],
+        )
+        .map_err(|err| err.downcast::<ContractError>().unwrap())?;
+
+        Ok(())
+    }
+
+    pub fn retract(
+        &self,
+        app: &mut App,
+        auction_id: u64,
+        sender: &Addr,
+    ) -> Result<(), ContractError> {
         app.execute_contract(
             sender.clone(),
             self.0.clone(),
             &ExecMsg::Retract {
+                auction_id,
                 receiver: Some(sender.to_string()),
             },
             &[],
@@ -75,19 +299,251 @@ impl AuctionContract {
         Ok(())
     }
 
-    pub fn query_info(&self, app: &App) -> StdResult<InfoResp> {
+    pub fn refund_all(
+        &self,
+        app: &mut App,
+        auction_id: u64,
+        sender: &Addr,
+        limit: Option<u32>,
+    ) -> Result<(), ContractError> {
+        app.execute_contract(
+            sender.clone(),
+            self.0.clone(),
+            &ExecMsg::RefundAll { auction_id, limit },
+            &[],
+        )
+        .map_err(|err| err.downcast::<ContractError>().unwrap())?;
+
+        Ok(())
+    }
+
+    pub fn recover_stray_funds(
+        &self,
+        app: &mut App,
+        sender: &Addr,
+        denom: &str,
+    ) -> Result<(), ContractError> {
+        app.execute_contract(
+            sender.clone(),
+            self.0.clone(),
+            &ExecMsg::RecoverStrayFunds {
+                denom: denom.to_string(),
+                recipient: None,
+            },
+            &[],
+        )
+        .map_err(|err| err.downcast::<ContractError>().unwrap())?;
+
+        Ok(())
+    }
+
+    pub fn propose_new_owner(
+        &self,
+        app: &mut App,
+        auction_id: u64,
+        sender: &Addr,
+        new_owner: &Addr,
+        expiry: Option<Scheduled>,
+    ) -> Result<(), ContractError> {
+        app.execute_contract(
+            sender.clone(),
+            self.0.clone(),
+            &ExecMsg::ProposeNewOwner {
+                auction_id,
+                new_owner: new_owner.to_string(),
+                expiry,
+            },
+            &[],
+        )
+        .map_err(|err| err.downcast::<ContractError>().unwrap())?;
+
+        Ok(())
+    }
+
+    pub fn accept_ownership(
+        &self,
+        app: &mut App,
+        auction_id: u64,
+        sender: &Addr,
+    ) -> Result<(), ContractError> {
+        app.execute_contract(
+            sender.clone(),
+            self.0.clone(),
+            &ExecMsg::AcceptOwnership { auction_id },
+            &[],
+        )
+        .map_err(|err| err.downcast::<ContractError>().unwrap())?;
+
+        Ok(())
+    }
+
+    pub fn cancel_ownership_transfer(
+        &self,
+        app: &mut App,
+        auction_id: u64,
+        sender: &Addr,
+    ) -> Result<(), ContractError> {
+        app.execute_contract(
+            sender.clone(),
+            self.0.clone(),
+            &ExecMsg::CancelOwnershipTransfer { auction_id },
+            &[],
+        )
+        .map_err(|err| err.downcast::<ContractError>().unwrap())?;
+
+        Ok(())
+    }
+
+    pub fn renounce_ownership(
+        &self,
+        app: &mut App,
+        auction_id: u64,
+        sender: &Addr,
+    ) -> Result<(), ContractError> {
+        app.execute_contract(
+            sender.clone(),
+            self.0.clone(),
+            &ExecMsg::RenounceOwnership { auction_id },
+            &[],
+        )
+        .map_err(|err| err.downcast::<ContractError>().unwrap())?;
+
+        Ok(())
+    }
+
+    pub fn query_info(&self, app: &App, auction_id: u64) -> StdResult<InfoResp> {
+        app.wrap()
+            .query_wasm_smart(self.0.clone(), &QueryMsg::Info { auction_id })
+    }
+
+    pub fn query_list_auctions(
+        &self,
+        app: &App,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+        active: Option<bool>,
+        owner: Option<&Addr>,
+    ) -> StdResult<ListAuctionsResp> {
+        app.wrap().query_wasm_smart(
+            self.0.clone(),
+            &QueryMsg::ListAuctions {
+                start_after,
+                limit,
+                active,
+                owner: owner.map(Addr::to_string),
+            },
+        )
+    }
+
+    pub fn query_all_bids(
+        &self,
+        app: &App,
+        auction_id: u64,
+        start_after: Option<&Addr>,
+        limit: Option<u32>,
+        order: Option<SortOrder>,
+    ) -> StdResult<AllBidsResp> {
+        app.wrap().query_wasm_smart(
+            self.0.clone(),
+            &QueryMsg::AllBids {
+                auction_id,
+                start_after: start_after.map(Addr::to_string),
+                limit,
+                order,
+            },
+        )
+    }
+
+    pub fn query_top_bids(
+        &self,
+        app: &App,
+        auction_id: u64,
+        limit: Option<u32>,
+    ) -> StdResult<AllBidsResp> {
+        app.wrap()
+            .query_wasm_smart(self.0.clone(), &QueryMsg::TopBids { auction_id, limit })
+    }
+
+    pub fn query_bid_history(
+        &self,
+        app: &App,
+        auction_id: u64,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<BidHistoryResp> {
+        app.wrap().query_wasm_smart(
+            self.0.clone(),
+            &QueryMsg::BidHistory {
+                auction_id,
+                start_after,
+                limit,
+            },
+        )
+    }
+
+    pub fn query_ownership(&self, app: &App, auction_id: u64) -> StdResult<OwnershipResp> {
+        app.wrap()
+            .query_wasm_smart(self.0.clone(), &QueryMsg::Ownership { auction_id })
+    }
+
+    pub fn query_address(&self, app: &App, auction_id: u64, address: &Addr) -> StdResult<BidsResp> {
+        app.wrap().query_wasm_smart(
+            self.0.clone(),
+            &QueryMsg::Bids {
+                auction_id,
+                address: address.to_string(),
+            },
+        )
+    }
+
+    pub fn query_highest_bid(&self, app: &App, auction_id: u64) -> StdResult<HighestBidResp> {
+        app.wrap()
+            .query_wasm_smart(self.0.clone(), &QueryMsg::HighestBid { auction_id })
+    }
+
+    pub fn query_min_next_bid(&self, app: &App, auction_id: u64) -> StdResult<Coin> {
+        app.wrap()
+            .query_wasm_smart(self.0.clone(), &QueryMsg::MinNextBid { auction_id })
+    }
+
+    pub fn query_allocation(
+        &self,
+        app: &App,
+        auction_id: u64,
+        address: &Addr,
+    ) -> StdResult<AllocationResp> {
+        app.wrap().query_wasm_smart(
+            self.0.clone(),
+            &QueryMsg::Allocation {
+                auction_id,
+                address: address.to_string(),
+            },
+        )
+    }
+
+    pub fn query_current_price(&self, app: &App, auction_id: u64) -> StdResult<Coin> {
+        app.wrap()
+            .query_wasm_smart(self.0.clone(), &QueryMsg::CurrentPrice { auction_id })
+    }
+
+    pub fn query_settlement(&self, app: &App, auction_id: u64) -> StdResult<SettlementResp> {
         app.wrap()
-            .query_wasm_smart(self.0.clone(), &QueryMsg::Info {})
+            .query_wasm_smart(self.0.clone(), &QueryMsg::Settlement { auction_id })
     }
 
-    pub fn query_address(&self, app: &App, address: &Addr) -> StdResult<Coin> {
+    pub fn query_solvency(&self, app: &App) -> StdResult<SolvencyResp> {
         app.wrap()
-            .query_wasm_smart(self.0.clone(), &QueryMsg::Bids { address: address.to_string() })
+            .query_wasm_smart(self.0.clone(), &QueryMsg::Solvency {})
     }
 
-    pub fn query_highest_bid(&self, app: &App) -> StdResult<HighestBidResp> {
+    pub fn query_winner(&self, app: &App, auction_id: u64) -> StdResult<WinnerResp> {
         app.wrap()
-            .query_wasm_smart(self.0.clone(), &QueryMsg::HighestBid {})
+            .query_wasm_smart(self.0.clone(), &QueryMsg::Winner { auction_id })
+    }
+
+    pub fn query_runner_up(&self, app: &App, auction_id: u64) -> StdResult<RunnerUpResp> {
+        app.wrap()
+            .query_wasm_smart(self.0.clone(), &QueryMsg::RunnerUp { auction_id })
     }
 }
 
@@ -95,4 +551,10 @@ impl From<AuctionContract> for Addr {
     fn from(contract: AuctionContract) -> Self {
         contract.0
     }
-}
\ No newline at end of file
+}
+
+impl From<Addr> for AuctionContract {
+    fn from(addr: Addr) -> Self {
+        Self(addr)
+    }
+}
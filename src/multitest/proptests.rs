@@ -0,0 +1,216 @@
+use cosmwasm_std::{coins, Addr, Decimal, Uint128};
+use cw_multi_test::App;
+use proptest::collection::vec;
+use proptest::prelude::*;
+
+use crate::msg::{CommissionMode, CreateAuctionMsg, InstantiateMsg, Reserve, Settlement};
+
+use super::contract::AuctionContract;
+
+const ATOM: &str = "atom";
+const OWNER: &str = "owner";
+const BIDDERS: [&str; 3] = ["bidder_one", "bidder_two", "bidder_three"];
+const AUCTIONS: usize = 2;
+
+#[derive(Clone, Debug)]
+enum Action {
+    Bid {
+        auction: usize,
+        bidder: usize,
+        amount: u128,
+    },
+    Retract {
+        auction: usize,
+        bidder: usize,
+    },
+    Close {
+        auction: usize,
+    },
+    RefundAll {
+        auction: usize,
+        limit: u32,
+    },
+}
+
+fn action() -> impl Strategy<Value = Action> {
+    let auction = 0..AUCTIONS;
+    let bidder = 0..BIDDERS.len();
+    prop_oneof![
+        4 => (auction.clone(), bidder.clone(), 1..40u128).prop_map(|(auction, bidder, amount)| {
+            Action::Bid {
+                auction,
+                bidder,
+                amount,
+            }
+        }),
+        2 => (auction.clone(), bidder).prop_map(|(auction, bidder)| Action::Retract {
+            auction,
+            bidder,
+        }),
+        1 => auction.clone().prop_map(|auction| Action::Close { auction }),
+        1 => (auction, 1..3u32).prop_map(|(auction, limit)| Action::RefundAll { auction, limit }),
+    ]
+}
+
+fn auction_msg() -> impl Strategy<Value = CreateAuctionMsg> {
+    (
+        0..=20u64,
+        any::<bool>(),
+        any::<bool>(),
+        any::<bool>(),
+        0..=50u64,
+        proptest::option::of(1..60u128),
+    )
+        .prop_map(
+            |(commission, on_settlement, second_price, refund_on_outbid, penalty, reserve)| {
+                CreateAuctionMsg {
+                    owner: None,
+                    commodity: "Gold".to_string(),
+                    bidding_denom: ATOM.to_string(),
+                    bidding_asset: None,
+                    commission: Decimal::percent(commission),
+                    start: None,
+                    end: None,
+                    anti_sniping: None,
+                    reserve: reserve.map(|amount| Reserve::Public {
+                        amount: Uint128::new(amount),
+                    }),
+                    starting_price: None,
+                    min_increment: None,
+                    mode: None,
+                    settlement: second_price.then_some(Settlement::SecondPrice),
+                    buy_now_price: None,
+                    refund_on_outbid: Some(refund_on_outbid),
+                    withdrawal_penalty: Some(Decimal::percent(penalty)),
+                    fee_recipients: None,
+                    commission_mode: on_settlement.then_some(CommissionMode::OnSettlement),
+                    accepted_denoms: None,
+                    stray_funds: None,
+                }
+            },
+        )
+}
+
+fn init(msgs: Vec<CreateAuctionMsg>) -> (App, AuctionContract, Vec<u64>) {
+    let mut app = App::new(|router, _api, storage| {
+        for account in BIDDERS.iter().chain([&OWNER]) {
+            router
+                .bank
+                .init_balance(storage, &Addr::unchecked(*account), coins(100, ATOM))
+                .unwrap();
+        }
+    });
+    let code_id = AuctionContract::store_code(&mut app);
+    let contract = AuctionContract::instantiate(
+        &mut app,
+        code_id,
+        &Addr::unchecked(OWNER),
+        "Auction contract",
+        None,
+        &InstantiateMsg {
+            fee_recipients: None,
+        },
+    )
+    .unwrap();
+    let auction_ids = msgs
+        .into_iter()
+        .map(|msg| {
+            contract
+                .create_auction(&mut app, &Addr::unchecked(OWNER), msg)
+                .unwrap()
+        })
+        .collect();
+
+    (app, contract, auction_ids)
+}
+
+/// Rejected actions are part of the sequence too, they must leave the accounting untouched.
+/// Bids the bidder can't afford are skipped, the bank fails them before the contract runs.
+fn apply(app: &mut App, contract: &AuctionContract, auction_ids: &[u64], action: Action) {
+    let owner = Addr::unchecked(OWNER);
+    let _ = match action {
+        Action::Bid {
+            auction,
+            bidder,
+            amount,
+        } => {
+            let balance = app.wrap().query_balance(BIDDERS[bidder], ATOM).unwrap();
+            if balance.amount.u128() < amount {
+                return;
+            }
+            contract.bid(
+                app,
+                auction_ids[auction],
+                &Addr::unchecked(BIDDERS[bidder]),
+                &coins(amount, ATOM),
+            )
+        }
+        Action::Retract { auction, bidder } => {
+            contract.retract(app, auction_ids[auction], &Addr::unchecked(BIDDERS[bidder]))
+        }
+        Action::Close { auction } => contract.close(app, auction_ids[auction], &owner),
+        Action::RefundAll { auction, limit } => {
+            contract.refund_all(app, auction_ids[auction], &owner, Some(limit))
+        }
+    };
+}
+
+/// The contract holds exactly what it owes bidders, and no tokens appear or vanish.
+fn assert_accounting(app: &App, contract: &AuctionContract) -> Result<(), TestCaseError> {
+    let solvency = contract.query_solvency(app).unwrap();
+    prop_assert!(solvency.solvent, "{solvency:?}");
+    for denom in &solvency.denoms {
+        prop_assert_eq!(denom.balance, denom.obligations);
+    }
+
+    let total: u128 = BIDDERS
+        .iter()
+        .chain([&OWNER, &contract.addr().as_str()])
+        .map(|account| {
+            app.wrap()
+                .query_balance(*account, ATOM)
+                .unwrap()
+                .amount
+                .u128()
+        })
+        .sum();
+    prop_assert_eq!(total, 400);
+
+    Ok(())
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn accounting_holds_after_any_actions(
+        msgs in vec(auction_msg(), AUCTIONS),
+        actions in vec(action(), 1..25),
+    ) {
+        let (mut app, contract, auction_ids) = init(msgs);
+
+        for action in actions {
+            apply(&mut app, &contract, &auction_ids, action);
+            assert_accounting(&app, &contract)?;
+        }
+
+        // Once every auction is closed and refunded nothing is left behind
+        for auction in 0..AUCTIONS {
+            apply(&mut app, &contract, &auction_ids, Action::Close { auction });
+            apply(
+                &mut app,
+                &contract,
+                &auction_ids,
+                Action::RefundAll {
+                    auction,
+                    limit: BIDDERS.len() as u32,
+                },
+            );
+        }
+        assert_accounting(&app, &contract)?;
+        prop_assert_eq!(
+            app.wrap().query_balance(contract.addr(), ATOM).unwrap().amount,
+            Uint128::zero()
+        );
+    }
+}
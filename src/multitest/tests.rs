@@ -1,8 +1,23 @@
-use cosmwasm_std::{coins, Addr, Coin, Decimal, Uint128};
-use cw_multi_test::App;
+use cosmwasm_std::testing::mock_env;
+use cosmwasm_std::{
+    coins, to_binary, Addr, Binary, Coin, Decimal, Deps, DepsMut, Empty, Env, MessageInfo,
+    Response, StdResult, Uint128,
+};
+use cw20::{BalanceResponse, Cw20Coin, Cw20QueryMsg};
+use cw721::OwnerOfResponse;
+use cw_multi_test::{App, ContractWrapper, Executor};
+use cw_utils::{Duration, Scheduled};
 
+use crate::commitment::{bid_commitment, reserve_commitment};
 use crate::error::ContractError;
-use crate::msg::{HighestBidResp, InfoResp};
+use crate::msg::{
+    AcceptedDenom, AllocationResp, AntiSniping, AssetKind, AuctionMode, BidHistoryEntry, BidResp,
+    BidsResp, CommissionMode, CreateAuctionMsg, DenomSolvency, ExchangeRateResponse, ExecMsg,
+    FeeRecipient, HighestBidResp, InfoResp, InstantiateMsg, ListAuctionsResp, Lot, MinIncrement,
+    OracleQueryMsg, Outcome, OwnershipResp, Rate, Reserve, ReserveReveal, RunnerUpResp, Settlement,
+    SettlementResp, SolvencyResp, SortOrder, StrayFunds, WinnerResp,
+};
+use crate::state;
 
 use super::contract::AuctionContract;
 const ATOM: &str = "atom";
@@ -10,7 +25,36 @@ const OWNER: &str = "owner";
 const BIDDER_ONE: &str = "bidder_one";
 const BIDDER_TWO: &str = "bidder_two";
 
-fn init_contract() -> (App, AuctionContract) {
+fn create_auction_msg() -> CreateAuctionMsg {
+    CreateAuctionMsg {
+        owner: None,
+        commodity: "Gold".to_string(),
+        bidding_denom: ATOM.to_string(),
+        bidding_asset: None,
+        commission: Decimal::percent(10),
+        start: None,
+        end: None,
+        anti_sniping: None,
+        reserve: None,
+        starting_price: None,
+        min_increment: None,
+        mode: None,
+        settlement: None,
+        buy_now_price: None,
+        refund_on_outbid: None,
+        withdrawal_penalty: None,
+        fee_recipients: None,
+        commission_mode: None,
+        accepted_denoms: None,
+        stray_funds: None,
+    }
+}
+
+fn init_contract() -> (App, AuctionContract, u64) {
+    init_contract_with(create_auction_msg()).unwrap()
+}
+
+fn init_contract_with(msg: CreateAuctionMsg) -> Result<(App, AuctionContract, u64), ContractError> {
     let mut app = App::new(|router, _api, storage| {
         router
             .bank
@@ -31,28 +75,186 @@ fn init_contract() -> (App, AuctionContract) {
         contract_id,
         &Addr::unchecked(OWNER),
         "Auction contract",
-        None,
-        "Gold",
-        ATOM.to_string(),
-        Decimal::percent(10),
+        Some(OWNER.to_string()),
+        &InstantiateMsg {
+            fee_recipients: None,
+        },
     )
     .unwrap();
-    (app, contract)
+    let auction_id = contract.create_auction(&mut app, &Addr::unchecked(OWNER), msg)?;
+    Ok((app, contract, auction_id))
+}
+
+const TOKEN_ID: &str = "token";
+
+/// Instantiates a CW721 contract with a single token owned by `OWNER`.
+fn init_nft(app: &mut App) -> Addr {
+    let code_id = app.store_code(Box::new(ContractWrapper::new(
+        cw721_base::entry::execute,
+        cw721_base::entry::instantiate,
+        cw721_base::entry::query,
+    )));
+    let nft = app
+        .instantiate_contract(
+            code_id,
+            Addr::unchecked(OWNER),
+            &cw721_base::InstantiateMsg {
+                name: "Collection".to_string(),
+                symbol: "COL".to_string(),
+                minter: OWNER.to_string(),
+            },
+            &[],
+            "NFT contract",
+            None,
+        )
+        .unwrap();
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        nft.clone(),
+        &cw721_base::ExecuteMsg::<cw721_base::Extension, Empty>::Mint {
+            token_id: TOKEN_ID.to_string(),
+            owner: OWNER.to_string(),
+            token_uri: None,
+            extension: None,
+        },
+        &[],
+    )
+    .unwrap();
+    nft
+}
+
+/// Instantiates a CW2981 contract with a single token owned by `OWNER`, paying
+/// `royalty_percentage` of every sale to `creator`.
+fn init_royalty_nft(app: &mut App, royalty_percentage: u64) -> Addr {
+    let code_id = app.store_code(Box::new(ContractWrapper::new(
+        cw2981_royalties::entry::execute,
+        cw2981_royalties::entry::instantiate,
+        cw2981_royalties::entry::query,
+    )));
+    let nft = app
+        .instantiate_contract(
+            code_id,
+            Addr::unchecked(OWNER),
+            &cw2981_royalties::InstantiateMsg {
+                name: "Collection".to_string(),
+                symbol: "COL".to_string(),
+                minter: OWNER.to_string(),
+            },
+            &[],
+            "NFT contract",
+            None,
+        )
+        .unwrap();
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        nft.clone(),
+        &cw2981_royalties::ExecuteMsg::Mint {
+            token_id: TOKEN_ID.to_string(),
+            owner: OWNER.to_string(),
+            token_uri: None,
+            extension: Some(cw2981_royalties::Metadata {
+                royalty_percentage: Some(royalty_percentage),
+                royalty_payment_address: Some("creator".to_string()),
+                ..Default::default()
+            }),
+        },
+        &[],
+    )
+    .unwrap();
+    nft
+}
+
+fn nft_owner(app: &App, nft: &Addr) -> String {
+    let resp: OwnerOfResponse = app
+        .wrap()
+        .query_wasm_smart(
+            nft,
+            &cw721_base::QueryMsg::<Empty>::OwnerOf {
+                token_id: TOKEN_ID.to_string(),
+                include_expired: None,
+            },
+        )
+        .unwrap();
+    resp.owner
+}
+
+/// Instantiates a CW20 contract with 100 tokens for the owner and each bidder.
+fn init_cw20(app: &mut App) -> Addr {
+    let code_id = app.store_code(Box::new(ContractWrapper::new(
+        cw20_base::contract::execute,
+        cw20_base::contract::instantiate,
+        cw20_base::contract::query,
+    )));
+    let initial_balances = [OWNER, BIDDER_ONE, BIDDER_TWO]
+        .into_iter()
+        .map(|address| Cw20Coin {
+            address: address.to_string(),
+            amount: Uint128::new(100),
+        })
+        .collect();
+    app.instantiate_contract(
+        code_id,
+        Addr::unchecked(OWNER),
+        &cw20_base::msg::InstantiateMsg {
+            name: "Token".to_string(),
+            symbol: "TKN".to_string(),
+            decimals: 6,
+            initial_balances,
+            mint: None,
+            marketing: None,
+        },
+        &[],
+        "CW20 contract",
+        None,
+    )
+    .unwrap()
+}
+
+fn cw20_balance(app: &App, token: &Addr, address: &str) -> u128 {
+    let resp: BalanceResponse = app
+        .wrap()
+        .query_wasm_smart(
+            token,
+            &Cw20QueryMsg::Balance {
+                address: address.to_string(),
+            },
+        )
+        .unwrap();
+    resp.balance.u128()
 }
 
 #[test]
 fn query_info_active() {
-    let (app, contract) = init_contract();
+    let (app, contract, auction_id) = init_contract();
 
-    let resp = AuctionContract::query_info(&contract, &app).unwrap();
+    let resp = AuctionContract::query_info(&contract, &app, auction_id).unwrap();
 
     assert_eq!(
         resp,
         InfoResp {
+            auction_id,
             owner: Addr::unchecked(OWNER),
             commodity: "Gold".to_string(),
+            lot: None,
             bidding_denom: ATOM.to_string(),
+            bidding_asset: AssetKind::Native,
             commission: Decimal::percent(10),
+            start: None,
+            end: None,
+            anti_sniping: None,
+            effective_end: None,
+            reserve: None,
+            starting_price: Uint128::zero(),
+            min_increment: None,
+            mode: AuctionMode::English,
+            settlement: Settlement::FirstPrice,
+            buy_now_price: None,
+            refund_on_outbid: false,
+            withdrawal_penalty: Decimal::zero(),
+            fee_recipients: vec![],
+            commission_mode: CommissionMode::PerBid,
+            accepted_denoms: vec![],
+            stray_funds: StrayFunds::Reject,
             active: true,
         }
     );
@@ -60,19 +262,38 @@ fn query_info_active() {
 
 #[test]
 fn query_info_closed() {
-    let (mut app, contract) = init_contract();
+    let (mut app, contract, auction_id) = init_contract();
 
-    AuctionContract::close(&contract, &mut app, &Addr::unchecked(OWNER)).unwrap();
+    AuctionContract::close(&contract, &mut app, auction_id, &Addr::unchecked(OWNER)).unwrap();
 
-    let resp = AuctionContract::query_info(&contract, &app).unwrap();
+    let resp = AuctionContract::query_info(&contract, &app, auction_id).unwrap();
 
     assert_eq!(
         resp,
         InfoResp {
+            auction_id,
             owner: Addr::unchecked(OWNER),
             commodity: "Gold".to_string(),
+            lot: None,
             bidding_denom: ATOM.to_string(),
+            bidding_asset: AssetKind::Native,
             commission: Decimal::percent(10),
+            start: None,
+            end: None,
+            anti_sniping: None,
+            effective_end: None,
+            reserve: None,
+            starting_price: Uint128::zero(),
+            min_increment: None,
+            mode: AuctionMode::English,
+            settlement: Settlement::FirstPrice,
+            buy_now_price: None,
+            refund_on_outbid: false,
+            withdrawal_penalty: Decimal::zero(),
+            fee_recipients: vec![],
+            commission_mode: CommissionMode::PerBid,
+            accepted_denoms: vec![],
+            stray_funds: StrayFunds::Reject,
             active: false,
         }
     );
@@ -80,11 +301,12 @@ fn query_info_closed() {
 
 #[test]
 fn owner_can_not_bid() {
-    let (mut app, contract) = init_contract();
+    let (mut app, contract, auction_id) = init_contract();
 
     let err = AuctionContract::bid(
         &contract,
         &mut app,
+        auction_id,
         &Addr::unchecked(OWNER),
         &coins(100, ATOM),
     )
@@ -95,13 +317,14 @@ fn owner_can_not_bid() {
 
 #[test]
 fn bid_closed_auction() {
-    let (mut app, contract) = init_contract();
+    let (mut app, contract, auction_id) = init_contract();
 
-    AuctionContract::close(&contract, &mut app, &Addr::unchecked(OWNER)).unwrap();
+    AuctionContract::close(&contract, &mut app, auction_id, &Addr::unchecked(OWNER)).unwrap();
 
     let err = AuctionContract::bid(
         &contract,
         &mut app,
+        auction_id,
         &Addr::unchecked(BIDDER_ONE),
         &coins(100, ATOM),
     )
@@ -112,45 +335,54 @@ fn bid_closed_auction() {
 
 #[test]
 fn query_bids_by_address_no_bids() {
-    let (app, contract) = init_contract();
+    let (app, contract, auction_id) = init_contract();
     let resp =
-        AuctionContract::query_address(&contract, &app, &Addr::unchecked(BIDDER_ONE)).unwrap();
+        AuctionContract::query_address(&contract, &app, auction_id, &Addr::unchecked(BIDDER_ONE))
+            .unwrap();
 
     assert_eq!(
         resp,
-        Coin {
-            denom: ATOM.to_string(),
-            amount: Uint128::zero(),
+        BidsResp {
+            bid: Coin {
+                denom: ATOM.to_string(),
+                amount: Uint128::zero(),
+            },
+            deposits: vec![],
         }
     );
 }
 
 #[test]
 fn query_bids_by_address() {
-    let (mut app, contract) = init_contract();
+    let (mut app, contract, auction_id) = init_contract();
     AuctionContract::bid(
         &contract,
         &mut app,
+        auction_id,
         &Addr::unchecked(BIDDER_ONE),
         &coins(100, ATOM),
     )
     .unwrap();
     let resp =
-        AuctionContract::query_address(&contract, &app, &Addr::unchecked(BIDDER_ONE)).unwrap();
+        AuctionContract::query_address(&contract, &app, auction_id, &Addr::unchecked(BIDDER_ONE))
+            .unwrap();
 
     assert_eq!(
         resp,
-        Coin {
-            denom: ATOM.to_string(),
-            amount: Uint128::new(90),
+        BidsResp {
+            bid: Coin {
+                denom: ATOM.to_string(),
+                amount: Uint128::new(90),
+            },
+            deposits: coins(90, ATOM),
         }
     );
 }
 
 #[test]
 fn query_highest_bid_no_bids() {
-    let (app, contract) = init_contract();
-    let resp = AuctionContract::query_highest_bid(&contract, &app).unwrap();
+    let (app, contract, auction_id) = init_contract();
+    let resp = AuctionContract::query_highest_bid(&contract, &app, auction_id).unwrap();
 
     assert_eq!(
         resp,
@@ -166,10 +398,11 @@ fn query_highest_bid_no_bids() {
 
 #[test]
 fn query_highest_bid() {
-    let (mut app, contract) = init_contract();
+    let (mut app, contract, auction_id) = init_contract();
     AuctionContract::bid(
         &contract,
         &mut app,
+        auction_id,
         &Addr::unchecked(BIDDER_ONE),
         &coins(50, ATOM),
     )
@@ -177,12 +410,13 @@ fn query_highest_bid() {
     AuctionContract::bid(
         &contract,
         &mut app,
+        auction_id,
         &Addr::unchecked(BIDDER_TWO),
         &coins(100, ATOM),
     )
     .unwrap();
 
-    let resp = AuctionContract::query_highest_bid(&contract, &app).unwrap();
+    let resp = AuctionContract::query_highest_bid(&contract, &app, auction_id).unwrap();
 
     assert_eq!(
         resp,
@@ -198,11 +432,12 @@ fn query_highest_bid() {
 
 #[test]
 fn retract_by_winner() {
-    let (mut app, contract) = init_contract();
+    let (mut app, contract, auction_id) = init_contract();
 
     AuctionContract::bid(
         &contract,
         &mut app,
+        auction_id,
         &Addr::unchecked(BIDDER_ONE),
         &coins(50, ATOM),
     )
@@ -210,24 +445,31 @@ fn retract_by_winner() {
     AuctionContract::bid(
         &contract,
         &mut app,
+        auction_id,
         &Addr::unchecked(BIDDER_TWO),
         &coins(100, ATOM),
     )
     .unwrap();
-    AuctionContract::close(&contract, &mut app, &Addr::unchecked(OWNER)).unwrap();
-    let err =
-        AuctionContract::retract(&contract, &mut app, &Addr::unchecked(BIDDER_TWO)).unwrap_err();
+    AuctionContract::close(&contract, &mut app, auction_id, &Addr::unchecked(OWNER)).unwrap();
+    let err = AuctionContract::retract(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_TWO),
+    )
+    .unwrap_err();
 
     assert_eq!(err, ContractError::NoFundsToRetract {});
 }
 
 #[test]
 fn retract_by_non_bidder() {
-    let (mut app, contract) = init_contract();
+    let (mut app, contract, auction_id) = init_contract();
 
     AuctionContract::bid(
         &contract,
         &mut app,
+        auction_id,
         &Addr::unchecked(BIDDER_ONE),
         &coins(50, ATOM),
     )
@@ -235,23 +477,26 @@ fn retract_by_non_bidder() {
     AuctionContract::bid(
         &contract,
         &mut app,
+        auction_id,
         &Addr::unchecked(BIDDER_TWO),
         &coins(100, ATOM),
     )
     .unwrap();
-    AuctionContract::close(&contract, &mut app, &Addr::unchecked(OWNER)).unwrap();
-    let err = AuctionContract::retract(&contract, &mut app, &Addr::unchecked(OWNER)).unwrap_err();
+    AuctionContract::close(&contract, &mut app, auction_id, &Addr::unchecked(OWNER)).unwrap();
+    let err = AuctionContract::retract(&contract, &mut app, auction_id, &Addr::unchecked(OWNER))
+        .unwrap_err();
 
     assert_eq!(err, ContractError::NoFundsToRetract {});
 }
 
 #[test]
 fn retract_by_bidder() {
-    let (mut app, contract) = init_contract();
+    let (mut app, contract, auction_id) = init_contract();
 
     AuctionContract::bid(
         &contract,
         &mut app,
+        auction_id,
         &Addr::unchecked(BIDDER_ONE),
         &coins(50, ATOM),
     )
@@ -259,15 +504,3188 @@ fn retract_by_bidder() {
     AuctionContract::bid(
         &contract,
         &mut app,
+        auction_id,
         &Addr::unchecked(BIDDER_TWO),
         &coins(100, ATOM),
     )
     .unwrap();
-    AuctionContract::close(&contract, &mut app, &Addr::unchecked(OWNER)).unwrap();
-    AuctionContract::retract(&contract, &mut app, &Addr::unchecked(BIDDER_ONE)).unwrap();
+    AuctionContract::close(&contract, &mut app, auction_id, &Addr::unchecked(OWNER)).unwrap();
+    AuctionContract::retract(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_ONE),
+    )
+    .unwrap();
 
     assert_eq!(
         app.wrap().query_all_balances(BIDDER_ONE).unwrap(),
         coins(95, ATOM)
     );
 }
+
+#[test]
+fn bid_before_start() {
+    let (mut app, contract, auction_id) = init_contract_with(CreateAuctionMsg {
+        start: Some(Scheduled::AtHeight(mock_env().block.height + 10)),
+        ..create_auction_msg()
+    })
+    .unwrap();
+
+    let err = AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_ONE),
+        &coins(50, ATOM),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::AuctionNotStarted {});
+
+    app.update_block(|block| block.height += 10);
+    AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_ONE),
+        &coins(50, ATOM),
+    )
+    .unwrap();
+}
+
+#[test]
+fn bid_after_end() {
+    let (mut app, contract, auction_id) = init_contract_with(CreateAuctionMsg {
+        end: Some(Scheduled::AtTime(mock_env().block.time.plus_seconds(60))),
+        ..create_auction_msg()
+    })
+    .unwrap();
+
+    app.update_block(|block| block.time = block.time.plus_seconds(60));
+    let err = AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_ONE),
+        &coins(50, ATOM),
+    )
+    .unwrap_err();
+
+    assert_eq!(err, ContractError::AuctionEnded {});
+}
+
+#[test]
+fn close_by_anyone_after_end() {
+    let (mut app, contract, auction_id) = init_contract_with(CreateAuctionMsg {
+        end: Some(Scheduled::AtHeight(mock_env().block.height + 10)),
+        ..create_auction_msg()
+    })
+    .unwrap();
+
+    AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_ONE),
+        &coins(50, ATOM),
+    )
+    .unwrap();
+
+    let err = AuctionContract::close(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_TWO),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Unauthorized {
+            owner: OWNER.to_string()
+        }
+    );
+
+    app.update_block(|block| block.height += 10);
+    AuctionContract::close(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_TWO),
+    )
+    .unwrap();
+
+    let resp = AuctionContract::query_info(&contract, &app, auction_id).unwrap();
+    assert!(!resp.active);
+    assert_eq!(
+        app.wrap().query_all_balances(OWNER).unwrap(),
+        coins(150, ATOM)
+    );
+}
+
+#[test]
+fn instantiate_with_invalid_schedule() {
+    let height = mock_env().block.height;
+
+    let err = init_contract_with(CreateAuctionMsg {
+        start: Some(Scheduled::AtHeight(height + 10)),
+        end: Some(Scheduled::AtHeight(height + 5)),
+        ..create_auction_msg()
+    })
+    .err()
+    .unwrap();
+    assert_eq!(err, ContractError::InvalidSchedule {});
+
+    let err = init_contract_with(CreateAuctionMsg {
+        start: Some(Scheduled::AtHeight(height + 10)),
+        end: Some(Scheduled::AtTime(mock_env().block.time.plus_seconds(60))),
+        ..create_auction_msg()
+    })
+    .err()
+    .unwrap();
+    assert_eq!(err, ContractError::InvalidSchedule {});
+}
+
+#[test]
+fn bid_near_end_extends_it() {
+    let height = mock_env().block.height;
+    let (mut app, contract, auction_id) = init_contract_with(CreateAuctionMsg {
+        end: Some(Scheduled::AtHeight(height + 10)),
+        anti_sniping: Some(AntiSniping {
+            window: Duration::Height(3),
+            extension: Duration::Height(5),
+        }),
+        ..create_auction_msg()
+    })
+    .unwrap();
+
+    // Outside of the window the end stays put
+    AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_ONE),
+        &coins(50, ATOM),
+    )
+    .unwrap();
+    let resp = AuctionContract::query_info(&contract, &app, auction_id).unwrap();
+    assert_eq!(resp.effective_end, Some(Scheduled::AtHeight(height + 10)));
+
+    app.update_block(|block| block.height += 8);
+    AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_TWO),
+        &coins(100, ATOM),
+    )
+    .unwrap();
+    let resp = AuctionContract::query_info(&contract, &app, auction_id).unwrap();
+    assert_eq!(resp.end, Some(Scheduled::AtHeight(height + 10)));
+    assert_eq!(resp.effective_end, Some(Scheduled::AtHeight(height + 15)));
+
+    // The original end has passed, but the auction is still open
+    app.update_block(|block| block.height += 4);
+    let err = AuctionContract::close(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_TWO),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Unauthorized {
+            owner: OWNER.to_string()
+        }
+    );
+}
+
+#[test]
+fn anti_sniping_requires_matching_end() {
+    let err = init_contract_with(CreateAuctionMsg {
+        anti_sniping: Some(AntiSniping {
+            window: Duration::Height(3),
+            extension: Duration::Height(5),
+        }),
+        ..create_auction_msg()
+    })
+    .err()
+    .unwrap();
+    assert_eq!(err, ContractError::InvalidAntiSniping {});
+
+    let err = init_contract_with(CreateAuctionMsg {
+        end: Some(Scheduled::AtTime(mock_env().block.time.plus_seconds(60))),
+        anti_sniping: Some(AntiSniping {
+            window: Duration::Time(10),
+            extension: Duration::Height(5),
+        }),
+        ..create_auction_msg()
+    })
+    .err()
+    .unwrap();
+    assert_eq!(err, ContractError::InvalidAntiSniping {});
+}
+
+#[test]
+fn public_reserve_not_met() {
+    let (mut app, contract, auction_id) = init_contract_with(CreateAuctionMsg {
+        reserve: Some(Reserve::Public {
+            amount: Uint128::new(100),
+        }),
+        ..create_auction_msg()
+    })
+    .unwrap();
+
+    AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_ONE),
+        &coins(50, ATOM),
+    )
+    .unwrap();
+    AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_TWO),
+        &coins(100, ATOM),
+    )
+    .unwrap();
+    AuctionContract::close(&contract, &mut app, auction_id, &Addr::unchecked(OWNER)).unwrap();
+
+    let resp = AuctionContract::query_winner(&contract, &app, auction_id).unwrap();
+    assert_eq!(
+        resp,
+        WinnerResp {
+            outcome: Outcome::ReserveNotMet,
+            address: "".to_string(),
+            bid: Coin {
+                denom: ATOM.to_string(),
+                amount: Uint128::zero(),
+            },
+        }
+    );
+
+    // Top bidder gets the deposit back, the owner kept only the commissions
+    AuctionContract::retract(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_TWO),
+    )
+    .unwrap();
+    assert_eq!(
+        app.wrap().query_all_balances(BIDDER_TWO).unwrap(),
+        coins(90, ATOM)
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(OWNER).unwrap(),
+        coins(115, ATOM)
+    );
+
+    let err = AuctionContract::retract(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_TWO),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NoFundsToRetract {});
+}
+
+#[test]
+fn hidden_reserve_revealed_at_close() {
+    let (mut app, contract, auction_id) = init_contract_with(CreateAuctionMsg {
+        reserve: Some(Reserve::Hidden {
+            commitment: reserve_commitment(Uint128::new(80), "salt"),
+        }),
+        ..create_auction_msg()
+    })
+    .unwrap();
+
+    AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_ONE),
+        &coins(100, ATOM),
+    )
+    .unwrap();
+
+    let err = AuctionContract::close_with_reserve(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(OWNER),
+        ReserveReveal {
+            amount: Uint128::new(70),
+            salt: "salt".to_string(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidReserveReveal {});
+
+    AuctionContract::close_with_reserve(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(OWNER),
+        ReserveReveal {
+            amount: Uint128::new(80),
+            salt: "salt".to_string(),
+        },
+    )
+    .unwrap();
+
+    let resp = AuctionContract::query_winner(&contract, &app, auction_id).unwrap();
+    assert_eq!(resp.outcome, Outcome::Sold);
+    assert_eq!(resp.address, BIDDER_ONE);
+    assert_eq!(
+        app.wrap().query_all_balances(OWNER).unwrap(),
+        coins(200, ATOM)
+    );
+}
+
+#[test]
+fn hidden_reserve_not_revealed() {
+    let (mut app, contract, auction_id) = init_contract_with(CreateAuctionMsg {
+        reserve: Some(Reserve::Hidden {
+            commitment: reserve_commitment(Uint128::new(10), "salt"),
+        }),
+        ..create_auction_msg()
+    })
+    .unwrap();
+
+    AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_ONE),
+        &coins(100, ATOM),
+    )
+    .unwrap();
+    AuctionContract::close(&contract, &mut app, auction_id, &Addr::unchecked(OWNER)).unwrap();
+
+    let resp = AuctionContract::query_winner(&contract, &app, auction_id).unwrap();
+    assert_eq!(resp.outcome, Outcome::ReserveNotMet);
+    AuctionContract::retract(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_ONE),
+    )
+    .unwrap();
+}
+
+#[test]
+fn hidden_reserve_closed_by_outsider() {
+    let (mut app, contract, auction_id) = init_contract_with(CreateAuctionMsg {
+        end: Some(Scheduled::AtHeight(mock_env().block.height + 10)),
+        reserve: Some(Reserve::Hidden {
+            commitment: reserve_commitment(Uint128::new(10), "salt"),
+        }),
+        ..create_auction_msg()
+    })
+    .unwrap();
+
+    AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_ONE),
+        &coins(100, ATOM),
+    )
+    .unwrap();
+    app.update_block(|block| block.height += 10);
+
+    // Closing without the reserve would cancel the sale
+    let err = AuctionContract::close(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_TWO),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::ReserveNotRevealed {});
+
+    AuctionContract::close_with_reserve(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_TWO),
+        ReserveReveal {
+            amount: Uint128::new(10),
+            salt: "salt".to_string(),
+        },
+    )
+    .unwrap();
+    let resp = AuctionContract::query_winner(&contract, &app, auction_id).unwrap();
+    assert_eq!(resp.outcome, Outcome::Sold);
+    assert_eq!(resp.address, BIDDER_ONE);
+}
+
+#[test]
+fn hidden_reserve_closable_after_grace() {
+    let (mut app, contract, auction_id) = init_contract_with(CreateAuctionMsg {
+        end: Some(Scheduled::AtHeight(mock_env().block.height + 10)),
+        reserve: Some(Reserve::Hidden {
+            commitment: reserve_commitment(Uint128::new(10), "salt"),
+        }),
+        ..create_auction_msg()
+    })
+    .unwrap();
+    let owner = Addr::unchecked(OWNER);
+    let bidder_one = Addr::unchecked(BIDDER_ONE);
+
+    AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &bidder_one,
+        &coins(100, ATOM),
+    )
+    .unwrap();
+    contract
+        .renounce_ownership(&mut app, auction_id, &owner)
+        .unwrap();
+    app.update_block(|block| block.height += 10);
+
+    let err = AuctionContract::close(&contract, &mut app, auction_id, &owner).unwrap_err();
+    assert_eq!(err, ContractError::ReserveNotRevealed {});
+    let err = AuctionContract::retract(&contract, &mut app, auction_id, &bidder_one).unwrap_err();
+    assert_eq!(err, ContractError::LeadingBidLocked {});
+
+    // Nobody revealed the reserve in time, so the auction closes without a sale
+    app.update_block(|block| block.height += 14_400);
+    AuctionContract::close(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_TWO),
+    )
+    .unwrap();
+    let resp = AuctionContract::query_winner(&contract, &app, auction_id).unwrap();
+    assert_eq!(resp.outcome, Outcome::ReserveNotMet);
+
+    AuctionContract::retract(&contract, &mut app, auction_id, &bidder_one).unwrap();
+    assert_eq!(
+        app.wrap().query_all_balances(BIDDER_ONE).unwrap(),
+        coins(90, ATOM)
+    );
+}
+
+#[test]
+fn bid_below_starting_price() {
+    let (mut app, contract, auction_id) = init_contract_with(CreateAuctionMsg {
+        starting_price: Some(Uint128::new(45)),
+        ..create_auction_msg()
+    })
+    .unwrap();
+
+    let resp = AuctionContract::query_min_next_bid(&contract, &app, auction_id).unwrap();
+    assert_eq!(resp, Coin::new(45, ATOM));
+
+    let err = AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_ONE),
+        &coins(40, ATOM),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::BidBelowMinimum {
+            min_bid: "45".to_string()
+        }
+    );
+
+    AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_ONE),
+        &coins(50, ATOM),
+    )
+    .unwrap();
+}
+
+#[test]
+fn bid_below_min_increment() {
+    let (mut app, contract, auction_id) = init_contract_with(CreateAuctionMsg {
+        min_increment: Some(MinIncrement::Amount(Uint128::new(10))),
+        ..create_auction_msg()
+    })
+    .unwrap();
+
+    AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_ONE),
+        &coins(50, ATOM),
+    )
+    .unwrap();
+
+    let resp = AuctionContract::query_min_next_bid(&contract, &app, auction_id).unwrap();
+    assert_eq!(resp, Coin::new(55, ATOM));
+
+    let err = AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_TWO),
+        &coins(60, ATOM),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::BidBelowMinimum {
+            min_bid: "55".to_string()
+        }
+    );
+
+    AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_TWO),
+        &coins(62, ATOM),
+    )
+    .unwrap();
+}
+
+#[test]
+fn min_increment_percent_of_leading_bid() {
+    let (mut app, contract, auction_id) = init_contract_with(CreateAuctionMsg {
+        min_increment: Some(MinIncrement::Percent(Decimal::percent(20))),
+        ..create_auction_msg()
+    })
+    .unwrap();
+
+    AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_ONE),
+        &coins(50, ATOM),
+    )
+    .unwrap();
+
+    let resp = AuctionContract::query_min_next_bid(&contract, &app, auction_id).unwrap();
+    assert_eq!(resp, Coin::new(54, ATOM));
+}
+
+#[test]
+fn auctions_are_independent() {
+    let (mut app, contract, first) = init_contract();
+    let second = contract
+        .create_auction(
+            &mut app,
+            &Addr::unchecked(BIDDER_ONE),
+            CreateAuctionMsg {
+                commodity: "Silver".to_string(),
+                ..create_auction_msg()
+            },
+        )
+        .unwrap();
+    assert_ne!(first, second);
+
+    AuctionContract::bid(
+        &contract,
+        &mut app,
+        first,
+        &Addr::unchecked(BIDDER_ONE),
+        &coins(50, ATOM),
+    )
+    .unwrap();
+    AuctionContract::bid(
+        &contract,
+        &mut app,
+        second,
+        &Addr::unchecked(BIDDER_TWO),
+        &coins(20, ATOM),
+    )
+    .unwrap();
+
+    let resp = AuctionContract::query_highest_bid(&contract, &app, first).unwrap();
+    assert_eq!(resp.address, BIDDER_ONE);
+    let resp = AuctionContract::query_highest_bid(&contract, &app, second).unwrap();
+    assert_eq!(resp.address, BIDDER_TWO);
+
+    // Only the owner of the second auction can close it
+    let err =
+        AuctionContract::close(&contract, &mut app, second, &Addr::unchecked(OWNER)).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Unauthorized {
+            owner: BIDDER_ONE.to_string()
+        }
+    );
+    AuctionContract::close(&contract, &mut app, second, &Addr::unchecked(BIDDER_ONE)).unwrap();
+
+    let resp = AuctionContract::query_info(&contract, &app, first).unwrap();
+    assert!(resp.active);
+    let resp = AuctionContract::query_info(&contract, &app, second).unwrap();
+    assert!(!resp.active);
+}
+
+#[test]
+fn list_auctions_filtered() {
+    let (mut app, contract, first) = init_contract();
+    let second = contract
+        .create_auction(&mut app, &Addr::unchecked(OWNER), create_auction_msg())
+        .unwrap();
+    let third = contract
+        .create_auction(&mut app, &Addr::unchecked(BIDDER_ONE), create_auction_msg())
+        .unwrap();
+    AuctionContract::close(&contract, &mut app, second, &Addr::unchecked(OWNER)).unwrap();
+
+    let ids = |resp: ListAuctionsResp| -> Vec<u64> {
+        resp.auctions.into_iter().map(|a| a.auction_id).collect()
+    };
+
+    let resp = contract
+        .query_list_auctions(&app, None, None, None, None)
+        .unwrap();
+    assert_eq!(ids(resp), vec![first, second, third]);
+
+    let resp = contract
+        .query_list_auctions(&app, Some(first), Some(1), None, None)
+        .unwrap();
+    assert_eq!(ids(resp), vec![second]);
+
+    let resp = contract
+        .query_list_auctions(&app, None, None, Some(true), None)
+        .unwrap();
+    assert_eq!(ids(resp), vec![first, third]);
+
+    let resp = contract
+        .query_list_auctions(&app, None, None, Some(true), Some(&Addr::unchecked(OWNER)))
+        .unwrap();
+    assert_eq!(ids(resp), vec![first]);
+}
+
+#[test]
+fn nft_delivered_to_winner() {
+    let (mut app, contract, _) = init_contract();
+    let nft = init_nft(&mut app);
+
+    let auction_id = contract
+        .create_auction_with_nft(
+            &mut app,
+            &Addr::unchecked(OWNER),
+            &nft,
+            TOKEN_ID,
+            create_auction_msg(),
+        )
+        .unwrap();
+    assert_eq!(nft_owner(&app, &nft), contract.addr().as_str());
+
+    let resp = AuctionContract::query_info(&contract, &app, auction_id).unwrap();
+    assert_eq!(resp.owner, OWNER);
+    assert_eq!(
+        resp.lot,
+        Some(Lot::Cw721 {
+            contract: nft.clone(),
+            token_id: TOKEN_ID.to_string(),
+        })
+    );
+
+    AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_ONE),
+        &coins(50, ATOM),
+    )
+    .unwrap();
+    AuctionContract::close(&contract, &mut app, auction_id, &Addr::unchecked(OWNER)).unwrap();
+
+    assert_eq!(nft_owner(&app, &nft), BIDDER_ONE);
+
+    // Plain CW721 contracts don't report royalties
+    let resp = contract.query_settlement(&app, auction_id).unwrap();
+    assert_eq!(resp.royalty_recipient, None);
+    assert_eq!(resp.seller_proceeds, coins(45, ATOM));
+}
+
+#[test]
+fn nft_returned_without_sale() {
+    let (mut app, contract, _) = init_contract();
+    let nft = init_nft(&mut app);
+
+    let auction_id = contract
+        .create_auction_with_nft(
+            &mut app,
+            &Addr::unchecked(OWNER),
+            &nft,
+            TOKEN_ID,
+            CreateAuctionMsg {
+                reserve: Some(Reserve::Public {
+                    amount: Uint128::new(1000),
+                }),
+                ..create_auction_msg()
+            },
+        )
+        .unwrap();
+
+    AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_ONE),
+        &coins(50, ATOM),
+    )
+    .unwrap();
+    AuctionContract::close(&contract, &mut app, auction_id, &Addr::unchecked(OWNER)).unwrap();
+
+    assert_eq!(nft_owner(&app, &nft), OWNER);
+}
+
+#[test]
+fn bid_with_cw20() {
+    let (mut app, contract, _) = init_contract();
+    let token = init_cw20(&mut app);
+    let auction_id = contract
+        .create_auction(
+            &mut app,
+            &Addr::unchecked(OWNER),
+            CreateAuctionMsg {
+                bidding_denom: token.to_string(),
+                bidding_asset: Some(AssetKind::Cw20),
+                ..create_auction_msg()
+            },
+        )
+        .unwrap();
+
+    let err = AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_ONE),
+        &coins(50, ATOM),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidDenom {
+            denom: token.to_string()
+        }
+    );
+
+    contract
+        .bid_cw20(
+            &mut app,
+            auction_id,
+            &Addr::unchecked(BIDDER_ONE),
+            &token,
+            50,
+        )
+        .unwrap();
+    contract
+        .bid_cw20(
+            &mut app,
+            auction_id,
+            &Addr::unchecked(BIDDER_TWO),
+            &token,
+            100,
+        )
+        .unwrap();
+    AuctionContract::close(&contract, &mut app, auction_id, &Addr::unchecked(OWNER)).unwrap();
+    AuctionContract::retract(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_ONE),
+    )
+    .unwrap();
+
+    // Commissions of 5 and 10 plus the winning 90
+    assert_eq!(cw20_balance(&app, &token, OWNER), 205);
+    assert_eq!(cw20_balance(&app, &token, BIDDER_ONE), 95);
+    assert_eq!(cw20_balance(&app, &token, BIDDER_TWO), 0);
+    assert_eq!(cw20_balance(&app, &token, contract.addr().as_str()), 0);
+}
+
+#[test]
+fn cw20_lot_delivered_to_winner() {
+    let (mut app, contract, _) = init_contract();
+    let token = init_cw20(&mut app);
+    let auction_id = contract
+        .create_auction_with_cw20(
+            &mut app,
+            &Addr::unchecked(OWNER),
+            &token,
+            40,
+            create_auction_msg(),
+        )
+        .unwrap();
+
+    let resp = AuctionContract::query_info(&contract, &app, auction_id).unwrap();
+    assert_eq!(
+        resp.lot,
+        Some(Lot::Cw20 {
+            contract: token.clone(),
+            amount: Uint128::new(40),
+        })
+    );
+
+    AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_ONE),
+        &coins(50, ATOM),
+    )
+    .unwrap();
+    AuctionContract::close(&contract, &mut app, auction_id, &Addr::unchecked(OWNER)).unwrap();
+
+    assert_eq!(cw20_balance(&app, &token, OWNER), 60);
+    assert_eq!(cw20_balance(&app, &token, BIDDER_ONE), 140);
+}
+
+fn sealed_auction_msg(unrevealed_penalty: Decimal) -> CreateAuctionMsg {
+    let height = mock_env().block.height;
+    CreateAuctionMsg {
+        end: Some(Scheduled::AtHeight(height + 10)),
+        mode: Some(AuctionMode::Sealed {
+            reveal_end: Scheduled::AtHeight(height + 20),
+            unrevealed_penalty,
+        }),
+        ..create_auction_msg()
+    }
+}
+
+#[test]
+fn sealed_bid_auction() {
+    let (mut app, contract, auction_id) =
+        init_contract_with(sealed_auction_msg(Decimal::zero())).unwrap();
+    let bidder_one = Addr::unchecked(BIDDER_ONE);
+    let bidder_two = Addr::unchecked(BIDDER_TWO);
+
+    let err = AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &bidder_one,
+        &coins(50, ATOM),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::WrongAuctionMode {});
+
+    let commitment = bid_commitment(&bidder_one, Uint128::new(50), "one");
+    contract
+        .commit_bid(
+            &mut app,
+            auction_id,
+            &bidder_one,
+            commitment,
+            &coins(60, ATOM),
+        )
+        .unwrap();
+    let commitment = bid_commitment(&bidder_two, Uint128::new(70), "two");
+    contract
+        .commit_bid(
+            &mut app,
+            auction_id,
+            &bidder_two,
+            commitment,
+            &coins(80, ATOM),
+        )
+        .unwrap();
+
+    let err = contract
+        .reveal_bid(&mut app, auction_id, &bidder_one, 50, "one")
+        .unwrap_err();
+    assert_eq!(err, ContractError::NotRevealPhase {});
+
+    app.update_block(|block| block.height += 10);
+
+    let err = contract
+        .reveal_bid(&mut app, auction_id, &bidder_one, 60, "one")
+        .unwrap_err();
+    assert_eq!(err, ContractError::InvalidBidReveal {});
+
+    contract
+        .reveal_bid(&mut app, auction_id, &bidder_one, 50, "one")
+        .unwrap();
+    contract
+        .reveal_bid(&mut app, auction_id, &bidder_two, 70, "two")
+        .unwrap();
+
+    let err = AuctionContract::close(&contract, &mut app, auction_id, &Addr::unchecked(OWNER))
+        .unwrap_err();
+    assert_eq!(err, ContractError::RevealPhaseNotOver {});
+
+    app.update_block(|block| block.height += 10);
+    AuctionContract::close(&contract, &mut app, auction_id, &bidder_one).unwrap();
+    AuctionContract::retract(&contract, &mut app, auction_id, &bidder_one).unwrap();
+
+    let resp = AuctionContract::query_winner(&contract, &app, auction_id).unwrap();
+    assert_eq!(
+        resp,
+        WinnerResp {
+            outcome: Outcome::Sold,
+            address: BIDDER_TWO.to_string(),
+            bid: Coin::new(70, ATOM),
+        }
+    );
+
+    // Commissions of 5 and 7 plus the winning 63
+    assert_eq!(
+        app.wrap().query_all_balances(OWNER).unwrap(),
+        coins(175, ATOM)
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(BIDDER_ONE).unwrap(),
+        coins(95, ATOM)
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(BIDDER_TWO).unwrap(),
+        coins(30, ATOM)
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(contract.addr()).unwrap(),
+        vec![]
+    );
+}
+
+#[test]
+fn sealed_tie_goes_to_earliest_commitment() {
+    let (mut app, contract, auction_id) =
+        init_contract_with(sealed_auction_msg(Decimal::zero())).unwrap();
+    let bidder_one = Addr::unchecked(BIDDER_ONE);
+    let bidder_two = Addr::unchecked(BIDDER_TWO);
+
+    let commitment = bid_commitment(&bidder_two, Uint128::new(50), "two");
+    contract
+        .commit_bid(
+            &mut app,
+            auction_id,
+            &bidder_two,
+            commitment,
+            &coins(50, ATOM),
+        )
+        .unwrap();
+    let commitment = bid_commitment(&bidder_one, Uint128::new(50), "one");
+    contract
+        .commit_bid(
+            &mut app,
+            auction_id,
+            &bidder_one,
+            commitment,
+            &coins(50, ATOM),
+        )
+        .unwrap();
+
+    app.update_block(|block| block.height += 10);
+    contract
+        .reveal_bid(&mut app, auction_id, &bidder_one, 50, "one")
+        .unwrap();
+    contract
+        .reveal_bid(&mut app, auction_id, &bidder_two, 50, "two")
+        .unwrap();
+
+    app.update_block(|block| block.height += 10);
+    AuctionContract::close(&contract, &mut app, auction_id, &Addr::unchecked(OWNER)).unwrap();
+
+    let resp = AuctionContract::query_winner(&contract, &app, auction_id).unwrap();
+    assert_eq!(resp.address, BIDDER_TWO);
+}
+
+#[test]
+fn sealed_auction_rejects_withdrawal_penalty() {
+    let err = init_contract_with(CreateAuctionMsg {
+        withdrawal_penalty: Some(Decimal::percent(10)),
+        ..sealed_auction_msg(Decimal::zero())
+    })
+    .err()
+    .unwrap();
+    assert_eq!(err, ContractError::WrongAuctionMode {});
+}
+
+#[test]
+fn sealed_zero_bid_rejected() {
+    let (mut app, contract, auction_id) =
+        init_contract_with(sealed_auction_msg(Decimal::zero())).unwrap();
+    let bidder_one = Addr::unchecked(BIDDER_ONE);
+
+    let commitment = bid_commitment(&bidder_one, Uint128::zero(), "one");
+    contract
+        .commit_bid(
+            &mut app,
+            auction_id,
+            &bidder_one,
+            commitment,
+            &coins(10, ATOM),
+        )
+        .unwrap();
+
+    app.update_block(|block| block.height += 10);
+    let err = contract
+        .reveal_bid(&mut app, auction_id, &bidder_one, 0, "one")
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::BidBelowMinimum {
+            min_bid: "1".to_string()
+        }
+    );
+}
+
+#[test]
+fn unrevealed_bid_penalised() {
+    let (mut app, contract, auction_id) =
+        init_contract_with(sealed_auction_msg(Decimal::percent(50))).unwrap();
+    let bidder_one = Addr::unchecked(BIDDER_ONE);
+    let bidder_two = Addr::unchecked(BIDDER_TWO);
+
+    let commitment = bid_commitment(&bidder_one, Uint128::new(40), "one");
+    contract
+        .commit_bid(
+            &mut app,
+            auction_id,
+            &bidder_one,
+            commitment,
+            &coins(40, ATOM),
+        )
+        .unwrap();
+    let commitment = bid_commitment(&bidder_two, Uint128::new(20), "two");
+    contract
+        .commit_bid(
+            &mut app,
+            auction_id,
+            &bidder_two,
+            commitment,
+            &coins(30, ATOM),
+        )
+        .unwrap();
+
+    app.update_block(|block| block.height += 10);
+    contract
+        .reveal_bid(&mut app, auction_id, &bidder_two, 20, "two")
+        .unwrap();
+
+    app.update_block(|block| block.height += 10);
+    AuctionContract::close(&contract, &mut app, auction_id, &Addr::unchecked(OWNER)).unwrap();
+    AuctionContract::retract(&contract, &mut app, auction_id, &bidder_one).unwrap();
+
+    let resp = AuctionContract::query_winner(&contract, &app, auction_id).unwrap();
+    assert_eq!(resp.address, BIDDER_TWO.to_string());
+
+    // Commission of 2, the winning 18 and half of the unrevealed deposit
+    assert_eq!(
+        app.wrap().query_all_balances(OWNER).unwrap(),
+        coins(140, ATOM)
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(BIDDER_ONE).unwrap(),
+        coins(80, ATOM)
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(BIDDER_TWO).unwrap(),
+        coins(80, ATOM)
+    );
+}
+
+#[test]
+fn sealed_auction_invalid_schedule() {
+    let height = mock_env().block.height;
+    let err = init_contract_with(CreateAuctionMsg {
+        end: Some(Scheduled::AtHeight(height + 10)),
+        mode: Some(AuctionMode::Sealed {
+            reveal_end: Scheduled::AtHeight(height + 10),
+            unrevealed_penalty: Decimal::zero(),
+        }),
+        ..create_auction_msg()
+    })
+    .err()
+    .unwrap();
+    assert_eq!(err, ContractError::InvalidSchedule {});
+
+    let err = init_contract_with(CreateAuctionMsg {
+        end: None,
+        ..sealed_auction_msg(Decimal::zero())
+    })
+    .err()
+    .unwrap();
+    assert_eq!(err, ContractError::InvalidSchedule {});
+}
+
+#[test]
+fn second_price_settlement() {
+    let (mut app, contract, auction_id) = init_contract_with(CreateAuctionMsg {
+        settlement: Some(Settlement::SecondPrice),
+        ..create_auction_msg()
+    })
+    .unwrap();
+
+    AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_ONE),
+        &coins(30, ATOM),
+    )
+    .unwrap();
+    AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_TWO),
+        &coins(40, ATOM),
+    )
+    .unwrap();
+    // Raising one's own lead keeps the runner-up
+    AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_TWO),
+        &coins(10, ATOM),
+    )
+    .unwrap();
+
+    let resp = AuctionContract::query_runner_up(&contract, &app, auction_id).unwrap();
+    assert_eq!(
+        resp,
+        RunnerUpResp {
+            address: BIDDER_ONE.to_string(),
+            bid: Coin::new(27, ATOM),
+        }
+    );
+
+    AuctionContract::close(&contract, &mut app, auction_id, &Addr::unchecked(OWNER)).unwrap();
+    AuctionContract::retract(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_ONE),
+    )
+    .unwrap();
+
+    // Commissions of 3, 4 and 1 plus the runner-up's 27, the winner gets 45 - 27 back
+    assert_eq!(
+        app.wrap().query_all_balances(OWNER).unwrap(),
+        coins(135, ATOM)
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(BIDDER_ONE).unwrap(),
+        coins(97, ATOM)
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(BIDDER_TWO).unwrap(),
+        coins(68, ATOM)
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(contract.addr()).unwrap(),
+        vec![]
+    );
+}
+
+#[test]
+fn second_price_raised_to_reserve() {
+    let (mut app, contract, auction_id) = init_contract_with(CreateAuctionMsg {
+        settlement: Some(Settlement::SecondPrice),
+        reserve: Some(Reserve::Public {
+            amount: Uint128::new(35),
+        }),
+        ..create_auction_msg()
+    })
+    .unwrap();
+
+    AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_ONE),
+        &coins(50, ATOM),
+    )
+    .unwrap();
+    AuctionContract::close(&contract, &mut app, auction_id, &Addr::unchecked(OWNER)).unwrap();
+
+    // Commission of 5 plus the reserve
+    assert_eq!(
+        app.wrap().query_all_balances(OWNER).unwrap(),
+        coins(140, ATOM)
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(BIDDER_ONE).unwrap(),
+        coins(60, ATOM)
+    );
+}
+
+#[test]
+fn sealed_second_price_settlement() {
+    let (mut app, contract, auction_id) = init_contract_with(CreateAuctionMsg {
+        settlement: Some(Settlement::SecondPrice),
+        ..sealed_auction_msg(Decimal::zero())
+    })
+    .unwrap();
+    let bidder_one = Addr::unchecked(BIDDER_ONE);
+    let bidder_two = Addr::unchecked(BIDDER_TWO);
+
+    let commitment = bid_commitment(&bidder_one, Uint128::new(50), "one");
+    contract
+        .commit_bid(
+            &mut app,
+            auction_id,
+            &bidder_one,
+            commitment,
+            &coins(50, ATOM),
+        )
+        .unwrap();
+    let commitment = bid_commitment(&bidder_two, Uint128::new(70), "two");
+    contract
+        .commit_bid(
+            &mut app,
+            auction_id,
+            &bidder_two,
+            commitment,
+            &coins(70, ATOM),
+        )
+        .unwrap();
+
+    app.update_block(|block| block.height += 10);
+    contract
+        .reveal_bid(&mut app, auction_id, &bidder_one, 50, "one")
+        .unwrap();
+    contract
+        .reveal_bid(&mut app, auction_id, &bidder_two, 70, "two")
+        .unwrap();
+
+    let resp = AuctionContract::query_runner_up(&contract, &app, auction_id).unwrap();
+    assert_eq!(resp.address, "");
+
+    app.update_block(|block| block.height += 10);
+    AuctionContract::close(&contract, &mut app, auction_id, &Addr::unchecked(OWNER)).unwrap();
+
+    let resp = AuctionContract::query_runner_up(&contract, &app, auction_id).unwrap();
+    assert_eq!(resp.address, BIDDER_ONE);
+
+    // Winner pays the runner-up's 45 on top of the commission of 7
+    assert_eq!(
+        app.wrap().query_all_balances(BIDDER_TWO).unwrap(),
+        coins(48, ATOM)
+    );
+}
+
+fn dutch_auction_msg() -> CreateAuctionMsg {
+    CreateAuctionMsg {
+        mode: Some(AuctionMode::Dutch {
+            start_price: Uint128::new(80),
+            floor_price: Uint128::new(20),
+            decrement: Uint128::new(10),
+            step: Duration::Height(5),
+        }),
+        ..create_auction_msg()
+    }
+}
+
+#[test]
+fn dutch_auction_sold_at_current_price() {
+    let (mut app, contract, auction_id) = init_contract_with(dutch_auction_msg()).unwrap();
+
+    let resp = AuctionContract::query_current_price(&contract, &app, auction_id).unwrap();
+    assert_eq!(resp, Coin::new(80, ATOM));
+
+    app.update_block(|block| block.height += 12);
+    let resp = AuctionContract::query_current_price(&contract, &app, auction_id).unwrap();
+    assert_eq!(resp, Coin::new(60, ATOM));
+
+    let err = AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_ONE),
+        &coins(50, ATOM),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::BidBelowMinimum {
+            min_bid: "60".to_string()
+        }
+    );
+
+    AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_ONE),
+        &coins(70, ATOM),
+    )
+    .unwrap();
+
+    let resp = AuctionContract::query_info(&contract, &app, auction_id).unwrap();
+    assert!(!resp.active);
+    let resp = AuctionContract::query_winner(&contract, &app, auction_id).unwrap();
+    assert_eq!(
+        resp,
+        WinnerResp {
+            outcome: Outcome::Sold,
+            address: BIDDER_ONE.to_string(),
+            bid: Coin::new(60, ATOM),
+        }
+    );
+
+    let err = AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_TWO),
+        &coins(80, ATOM),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::AuctionClosed {});
+
+    // The excess 10 is refunded
+    assert_eq!(
+        app.wrap().query_all_balances(OWNER).unwrap(),
+        coins(160, ATOM)
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(BIDDER_ONE).unwrap(),
+        coins(40, ATOM)
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(contract.addr()).unwrap(),
+        vec![]
+    );
+}
+
+#[test]
+fn dutch_price_stops_at_floor() {
+    let (mut app, contract, auction_id) = init_contract_with(dutch_auction_msg()).unwrap();
+
+    app.update_block(|block| block.height += 1000);
+    let resp = AuctionContract::query_current_price(&contract, &app, auction_id).unwrap();
+    assert_eq!(resp, Coin::new(20, ATOM));
+}
+
+#[test]
+fn dutch_auction_invalid() {
+    let err = init_contract_with(CreateAuctionMsg {
+        mode: Some(AuctionMode::Dutch {
+            start_price: Uint128::new(10),
+            floor_price: Uint128::new(20),
+            decrement: Uint128::new(1),
+            step: Duration::Height(1),
+        }),
+        ..create_auction_msg()
+    })
+    .err()
+    .unwrap();
+    assert_eq!(err, ContractError::InvalidDutchPrice {});
+
+    let err = init_contract_with(CreateAuctionMsg {
+        reserve: Some(Reserve::Public {
+            amount: Uint128::new(30),
+        }),
+        ..dutch_auction_msg()
+    })
+    .err()
+    .unwrap();
+    assert_eq!(err, ContractError::WrongAuctionMode {});
+
+    let err = init_contract_with(CreateAuctionMsg {
+        withdrawal_penalty: Some(Decimal::percent(10)),
+        ..dutch_auction_msg()
+    })
+    .err()
+    .unwrap();
+    assert_eq!(err, ContractError::WrongAuctionMode {});
+}
+
+fn multi_unit_auction_msg() -> CreateAuctionMsg {
+    CreateAuctionMsg {
+        commodity: "Ticket".to_string(),
+        mode: Some(AuctionMode::MultiUnit { quantity: 5 }),
+        ..create_auction_msg()
+    }
+}
+
+#[test]
+fn multi_unit_uniform_clearing_price() {
+    let (mut app, contract, auction_id) = init_contract_with(multi_unit_auction_msg()).unwrap();
+    let bidder_one = Addr::unchecked(BIDDER_ONE);
+    let bidder_two = Addr::unchecked(BIDDER_TWO);
+
+    contract
+        .bid_units(&mut app, auction_id, &bidder_one, 3, 10, &coins(30, ATOM))
+        .unwrap();
+    contract
+        .bid_units(&mut app, auction_id, &bidder_two, 3, 8, &coins(24, ATOM))
+        .unwrap();
+    contract
+        .bid_units(&mut app, auction_id, &bidder_one, 1, 5, &coins(5, ATOM))
+        .unwrap();
+
+    AuctionContract::close(&contract, &mut app, auction_id, &Addr::unchecked(OWNER)).unwrap();
+
+    // Three units at 10 and two of three at 8 are accepted, all paying 8
+    let resp = contract
+        .query_allocation(&app, auction_id, &bidder_one)
+        .unwrap();
+    assert_eq!(
+        resp,
+        AllocationResp {
+            quantity: 3,
+            price: Coin::new(8, ATOM),
+        }
+    );
+    let resp = contract
+        .query_allocation(&app, auction_id, &bidder_two)
+        .unwrap();
+    assert_eq!(resp.quantity, 2);
+
+    assert_eq!(
+        app.wrap().query_all_balances(OWNER).unwrap(),
+        coins(140, ATOM)
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(BIDDER_ONE).unwrap(),
+        coins(76, ATOM)
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(BIDDER_TWO).unwrap(),
+        coins(84, ATOM)
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(contract.addr()).unwrap(),
+        vec![]
+    );
+
+    let err = AuctionContract::retract(&contract, &mut app, auction_id, &bidder_one).unwrap_err();
+    assert_eq!(err, ContractError::NoFundsToRetract {});
+}
+
+#[test]
+fn multi_unit_invalid_bids() {
+    let (mut app, contract, auction_id) = init_contract_with(multi_unit_auction_msg()).unwrap();
+    let bidder_one = Addr::unchecked(BIDDER_ONE);
+
+    let err = contract
+        .bid_units(&mut app, auction_id, &bidder_one, 3, 10, &coins(20, ATOM))
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::IncorrectDeposit {
+            expected: "30".to_string()
+        }
+    );
+
+    let err = contract
+        .bid_units(&mut app, auction_id, &bidder_one, 6, 1, &coins(6, ATOM))
+        .unwrap_err();
+    assert_eq!(err, ContractError::InvalidQuantity {});
+
+    let err = AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &bidder_one,
+        &coins(10, ATOM),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::WrongAuctionMode {});
+}
+
+#[test]
+fn multi_unit_auction_invalid() {
+    let height = mock_env().block.height;
+    let err = init_contract_with(CreateAuctionMsg {
+        end: Some(Scheduled::AtHeight(height + 10)),
+        anti_sniping: Some(AntiSniping {
+            window: Duration::Height(3),
+            extension: Duration::Height(5),
+        }),
+        ..multi_unit_auction_msg()
+    })
+    .err()
+    .unwrap();
+    assert_eq!(err, ContractError::WrongAuctionMode {});
+
+    let err = init_contract_with(CreateAuctionMsg {
+        withdrawal_penalty: Some(Decimal::percent(10)),
+        ..multi_unit_auction_msg()
+    })
+    .err()
+    .unwrap();
+    assert_eq!(err, ContractError::WrongAuctionMode {});
+}
+
+#[test]
+fn buy_now_closes_auction() {
+    let (mut app, contract, auction_id) = init_contract_with(CreateAuctionMsg {
+        buy_now_price: Some(Uint128::new(45)),
+        ..create_auction_msg()
+    })
+    .unwrap();
+
+    let resp = AuctionContract::query_info(&contract, &app, auction_id).unwrap();
+    assert_eq!(resp.buy_now_price, Some(Uint128::new(45)));
+
+    AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_ONE),
+        &coins(20, ATOM),
+    )
+    .unwrap();
+    AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_TWO),
+        &coins(60, ATOM),
+    )
+    .unwrap();
+
+    let resp = AuctionContract::query_info(&contract, &app, auction_id).unwrap();
+    assert!(!resp.active);
+    let resp = AuctionContract::query_winner(&contract, &app, auction_id).unwrap();
+    assert_eq!(resp.outcome, Outcome::Sold);
+    assert_eq!(resp.address, BIDDER_TWO);
+
+    AuctionContract::retract(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_ONE),
+    )
+    .unwrap();
+
+    // Commissions of 2 and 4 plus the buy-now price, only the 49 it takes is taxed
+    assert_eq!(
+        app.wrap().query_all_balances(OWNER).unwrap(),
+        coins(151, ATOM)
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(BIDDER_ONE).unwrap(),
+        coins(98, ATOM)
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(BIDDER_TWO).unwrap(),
+        coins(51, ATOM)
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(contract.addr()).unwrap(),
+        vec![]
+    );
+}
+
+#[test]
+fn buy_now_tops_up_deposit() {
+    let (mut app, contract, auction_id) = init_contract_with(CreateAuctionMsg {
+        buy_now_price: Some(Uint128::new(45)),
+        ..create_auction_msg()
+    })
+    .unwrap();
+
+    AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_ONE),
+        &coins(20, ATOM),
+    )
+    .unwrap();
+    AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_ONE),
+        &coins(80, ATOM),
+    )
+    .unwrap();
+
+    // 18 deposited already, 29 more leave the missing 27 after commission
+    let resp = AuctionContract::query_winner(&contract, &app, auction_id).unwrap();
+    assert_eq!(resp.outcome, Outcome::Sold);
+    assert_eq!(
+        app.wrap().query_all_balances(BIDDER_ONE).unwrap(),
+        coins(51, ATOM)
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(OWNER).unwrap(),
+        coins(149, ATOM)
+    );
+}
+
+#[test]
+fn buy_now_above_min_increment() {
+    let (mut app, contract, auction_id) = init_contract_with(CreateAuctionMsg {
+        buy_now_price: Some(Uint128::new(50)),
+        min_increment: Some(MinIncrement::Amount(Uint128::new(10))),
+        ..create_auction_msg()
+    })
+    .unwrap();
+
+    AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_ONE),
+        &coins(50, ATOM),
+    )
+    .unwrap();
+    // The next bid would need 55, the buy-now price still wins at 50
+    AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_TWO),
+        &coins(90, ATOM),
+    )
+    .unwrap();
+
+    let resp = AuctionContract::query_winner(&contract, &app, auction_id).unwrap();
+    assert_eq!(resp.outcome, Outcome::Sold);
+    assert_eq!(resp.address, BIDDER_TWO);
+    assert_eq!(
+        app.wrap().query_all_balances(BIDDER_TWO).unwrap(),
+        coins(45, ATOM)
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(OWNER).unwrap(),
+        coins(160, ATOM)
+    );
+}
+
+#[test]
+fn buy_now_below_reserve() {
+    let err = init_contract_with(CreateAuctionMsg {
+        buy_now_price: Some(Uint128::new(20)),
+        reserve: Some(Reserve::Public {
+            amount: Uint128::new(30),
+        }),
+        ..create_auction_msg()
+    })
+    .err()
+    .unwrap();
+    assert_eq!(err, ContractError::InvalidBuyNowPrice {});
+}
+
+#[test]
+fn refund_on_outbid() {
+    let (mut app, contract, auction_id) = init_contract_with(CreateAuctionMsg {
+        refund_on_outbid: Some(true),
+        ..create_auction_msg()
+    })
+    .unwrap();
+
+    AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_ONE),
+        &coins(20, ATOM),
+    )
+    .unwrap();
+    AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_TWO),
+        &coins(40, ATOM),
+    )
+    .unwrap();
+
+    // Only the commission of 2 is kept
+    assert_eq!(
+        app.wrap().query_all_balances(BIDDER_ONE).unwrap(),
+        coins(98, ATOM)
+    );
+    let resp =
+        AuctionContract::query_address(&contract, &app, auction_id, &Addr::unchecked(BIDDER_ONE))
+            .unwrap();
+    assert_eq!(resp.bid, Coin::new(0, ATOM));
+
+    AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_ONE),
+        &coins(50, ATOM),
+    )
+    .unwrap();
+    AuctionContract::close(&contract, &mut app, auction_id, &Addr::unchecked(OWNER)).unwrap();
+
+    let err = AuctionContract::retract(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_TWO),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NoFundsToRetract {});
+
+    // Commissions of 2, 4 and 5 plus the winning 45
+    assert_eq!(
+        app.wrap().query_all_balances(OWNER).unwrap(),
+        coins(156, ATOM)
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(BIDDER_ONE).unwrap(),
+        coins(48, ATOM)
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(BIDDER_TWO).unwrap(),
+        coins(96, ATOM)
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(contract.addr()).unwrap(),
+        vec![]
+    );
+}
+
+#[test]
+fn refund_all_losing_bidders() {
+    let (mut app, contract, auction_id) = init_contract();
+    let bidder_three = Addr::unchecked("bidder_three");
+    app.send_tokens(
+        Addr::unchecked(OWNER),
+        bidder_three.clone(),
+        &coins(50, ATOM),
+    )
+    .unwrap();
+
+    AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_ONE),
+        &coins(20, ATOM),
+    )
+    .unwrap();
+    AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &bidder_three,
+        &coins(30, ATOM),
+    )
+    .unwrap();
+    AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_TWO),
+        &coins(40, ATOM),
+    )
+    .unwrap();
+
+    let err = contract
+        .refund_all(&mut app, auction_id, &bidder_three, None)
+        .unwrap_err();
+    assert_eq!(err, ContractError::AuctionNotClosed {});
+
+    AuctionContract::close(&contract, &mut app, auction_id, &Addr::unchecked(OWNER)).unwrap();
+
+    contract
+        .refund_all(&mut app, auction_id, &bidder_three, Some(1))
+        .unwrap();
+    assert_eq!(
+        app.wrap().query_all_balances(BIDDER_ONE).unwrap(),
+        coins(98, ATOM)
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(&bidder_three).unwrap(),
+        coins(20, ATOM)
+    );
+
+    contract
+        .refund_all(&mut app, auction_id, &bidder_three, None)
+        .unwrap();
+    assert_eq!(
+        app.wrap().query_all_balances(&bidder_three).unwrap(),
+        coins(47, ATOM)
+    );
+
+    // The winner's payment is left alone
+    let err = AuctionContract::retract(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_ONE),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NoFundsToRetract {});
+    assert_eq!(
+        app.wrap().query_all_balances(BIDDER_TWO).unwrap(),
+        coins(60, ATOM)
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(contract.addr()).unwrap(),
+        vec![]
+    );
+}
+
+#[test]
+fn withdraw_while_active() {
+    let (mut app, contract, auction_id) = init_contract_with(CreateAuctionMsg {
+        withdrawal_penalty: Some(Decimal::percent(10)),
+        ..create_auction_msg()
+    })
+    .unwrap();
+
+    AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_ONE),
+        &coins(20, ATOM),
+    )
+    .unwrap();
+    AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_TWO),
+        &coins(30, ATOM),
+    )
+    .unwrap();
+
+    let err = AuctionContract::retract(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_TWO),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::LeadingBidLocked {});
+
+    AuctionContract::retract(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_ONE),
+    )
+    .unwrap();
+
+    // Commissions of 2 and 3 plus the penalty of 10% of 18
+    assert_eq!(
+        app.wrap().query_all_balances(OWNER).unwrap(),
+        coins(106, ATOM)
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(BIDDER_ONE).unwrap(),
+        coins(97, ATOM)
+    );
+
+    let resp = AuctionContract::query_info(&contract, &app, auction_id).unwrap();
+    assert!(resp.active);
+}
+
+#[test]
+fn withdraw_while_active_only_in_english_auction() {
+    let (mut app, contract, auction_id) =
+        init_contract_with(sealed_auction_msg(Decimal::zero())).unwrap();
+    let bidder_one = Addr::unchecked(BIDDER_ONE);
+
+    let commitment = bid_commitment(&bidder_one, Uint128::new(10), "one");
+    contract
+        .commit_bid(
+            &mut app,
+            auction_id,
+            &bidder_one,
+            commitment,
+            &coins(10, ATOM),
+        )
+        .unwrap();
+
+    let err = AuctionContract::retract(&contract, &mut app, auction_id, &bidder_one).unwrap_err();
+    assert_eq!(err, ContractError::AuctionNotClosed {});
+}
+
+/// Stands in for a 1.x deployment: a closed auction won by `BIDDER_TWO`, with the bid of
+/// `BIDDER_ONE` still to be retracted.
+fn legacy_instantiate(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    version: String,
+) -> StdResult<Response> {
+    cw2::set_contract_version(deps.storage, "auction-contract", version)?;
+    state::v1::BASE_INFO.save(
+        deps.storage,
+        &state::v1::BaseInfo {
+            owner: Addr::unchecked(OWNER),
+            commodity: "Gold".to_string(),
+            bidding_denom: ATOM.to_string(),
+            commission: Decimal::percent(10),
+            active: false,
+        },
+    )?;
+    state::v1::BIDS.save(
+        deps.storage,
+        Addr::unchecked(BIDDER_ONE),
+        &Coin::new(18, ATOM),
+    )?;
+    state::v1::BIDS.save(
+        deps.storage,
+        Addr::unchecked(BIDDER_TWO),
+        &Coin::new(36, ATOM),
+    )?;
+    state::v1::HIGHEST_BID.save(
+        deps.storage,
+        &state::Bid {
+            address: Addr::unchecked(BIDDER_TWO),
+            bid: Coin::new(40, ATOM),
+        },
+    )?;
+    Ok(Response::new())
+}
+
+/// 1.x left this loser's bid behind after they retracted it.
+fn legacy_stale_instantiate(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    version: String,
+) -> StdResult<Response> {
+    let resp = legacy_instantiate(deps.branch(), env, info, version)?;
+    state::v1::BIDS.save(
+        deps.storage,
+        Addr::unchecked("bidder_three"),
+        &Coin::new(12, ATOM),
+    )?;
+    Ok(resp)
+}
+
+fn init_legacy_contract(
+    instantiate: fn(DepsMut, Env, MessageInfo, String) -> StdResult<Response>,
+    version: &str,
+) -> (App, AuctionContract) {
+    let (mut app, _, _) = init_contract();
+    let code_id = app.store_code(Box::new(ContractWrapper::new(
+        crate::execute,
+        instantiate,
+        crate::query,
+    )));
+    let contract = app
+        .instantiate_contract(
+            code_id,
+            Addr::unchecked(OWNER),
+            &version,
+            &[],
+            "Legacy auction contract",
+            Some(OWNER.to_string()),
+        )
+        .unwrap();
+    app.send_tokens(Addr::unchecked(OWNER), contract.clone(), &coins(18, ATOM))
+        .unwrap();
+    (app, contract.into())
+}
+
+#[test]
+fn migrate_from_v1() {
+    let (mut app, contract) = init_legacy_contract(legacy_instantiate, "1.0.0");
+    let code_id = AuctionContract::store_code(&mut app);
+    contract
+        .migrate(&mut app, &Addr::unchecked(OWNER), code_id)
+        .unwrap();
+
+    let version = cw2::query_contract_info(&app.wrap(), contract.addr()).unwrap();
+    assert_eq!(version.version, env!("CARGO_PKG_VERSION"));
+
+    let resp = AuctionContract::query_info(&contract, &app, 1).unwrap();
+    assert_eq!(resp.owner, OWNER);
+    assert!(!resp.active);
+
+    // Only the losing bid is still held, the winner was paid out by 1.x
+    let resp = contract.query_solvency(&app).unwrap();
+    assert_eq!(
+        resp.denoms,
+        vec![DenomSolvency {
+            denom: ATOM.to_string(),
+            balance: Uint128::new(18),
+            obligations: Uint128::new(18),
+            surplus: Uint128::zero(),
+            deficit: Uint128::zero(),
+        }]
+    );
+    let resp = AuctionContract::query_winner(&contract, &app, 1).unwrap();
+    assert_eq!(
+        resp,
+        WinnerResp {
+            outcome: Outcome::Sold,
+            address: BIDDER_TWO.to_string(),
+            bid: Coin::new(40, ATOM),
+        }
+    );
+
+    AuctionContract::retract(&contract, &mut app, 1, &Addr::unchecked(BIDDER_ONE)).unwrap();
+    assert_eq!(
+        app.wrap().query_all_balances(BIDDER_ONE).unwrap(),
+        coins(118, ATOM)
+    );
+
+    let auction_id = contract
+        .create_auction(&mut app, &Addr::unchecked(OWNER), create_auction_msg())
+        .unwrap();
+    assert_eq!(auction_id, 2);
+
+    // Migrated deposits are indexed
+    let resp = contract.query_top_bids(&app, 1, None).unwrap();
+    assert_eq!(
+        resp.bids,
+        vec![BidResp {
+            address: BIDDER_TWO.to_string(),
+            bid: Coin::new(36, ATOM),
+        }]
+    );
+}
+
+#[test]
+fn migrate_from_v1_refuses_stale_bids() {
+    let (mut app, contract) = init_legacy_contract(legacy_stale_instantiate, "1.0.0");
+    let code_id = AuctionContract::store_code(&mut app);
+
+    // Paying both losing bids out of the 18 held would take other bidders' funds
+    let err = contract
+        .migrate(&mut app, &Addr::unchecked(OWNER), code_id)
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::UnfundedLegacyBids {
+            owed: "30atom".to_string(),
+            balance: "18atom".to_string(),
+        }
+    );
+    let version = cw2::query_contract_info(&app.wrap(), contract.addr()).unwrap();
+    assert_eq!(version.version, "1.0.0");
+}
+
+#[test]
+fn migrate_refuses_downgrade() {
+    let (mut app, contract) = init_legacy_contract(legacy_instantiate, "3.0.0");
+    let code_id = AuctionContract::store_code(&mut app);
+
+    let err = contract
+        .migrate(&mut app, &Addr::unchecked(OWNER), code_id)
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Downgrade {
+            stored: "3.0.0".to_string(),
+            current: env!("CARGO_PKG_VERSION").to_string(),
+        }
+    );
+}
+
+#[test]
+fn ownership_transfer() {
+    let (mut app, contract, auction_id) = init_contract();
+    let owner = Addr::unchecked(OWNER);
+    let new_owner = Addr::unchecked(BIDDER_ONE);
+
+    contract
+        .propose_new_owner(&mut app, auction_id, &owner, &new_owner, None)
+        .unwrap();
+    let resp = contract.query_ownership(&app, auction_id).unwrap();
+    assert_eq!(
+        resp,
+        OwnershipResp {
+            owner: Some(owner.clone()),
+            pending_owner: Some(new_owner.clone()),
+            pending_expiry: None,
+        }
+    );
+
+    let err = contract
+        .accept_ownership(&mut app, auction_id, &Addr::unchecked(BIDDER_TWO))
+        .unwrap_err();
+    assert_eq!(err, ContractError::NoOwnershipTransfer {});
+
+    contract
+        .accept_ownership(&mut app, auction_id, &new_owner)
+        .unwrap();
+    let resp = contract.query_ownership(&app, auction_id).unwrap();
+    assert_eq!(resp.owner, Some(new_owner.clone()));
+    assert_eq!(resp.pending_owner, None);
+
+    AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_TWO),
+        &coins(50, ATOM),
+    )
+    .unwrap();
+
+    let err = AuctionContract::close(&contract, &mut app, auction_id, &owner).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Unauthorized {
+            owner: BIDDER_ONE.to_string()
+        }
+    );
+    AuctionContract::close(&contract, &mut app, auction_id, &new_owner).unwrap();
+
+    // The new owner gets the commission and the proceeds
+    assert_eq!(
+        app.wrap().query_all_balances(BIDDER_ONE).unwrap(),
+        coins(150, ATOM)
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(OWNER).unwrap(),
+        coins(100, ATOM)
+    );
+}
+
+#[test]
+fn ownership_transfer_expired_or_cancelled() {
+    let (mut app, contract, auction_id) = init_contract();
+    let owner = Addr::unchecked(OWNER);
+    let new_owner = Addr::unchecked(BIDDER_ONE);
+    let expiry = Scheduled::AtHeight(mock_env().block.height + 5);
+
+    contract
+        .propose_new_owner(&mut app, auction_id, &owner, &new_owner, Some(expiry))
+        .unwrap();
+    app.update_block(|block| block.height += 5);
+    let err = contract
+        .accept_ownership(&mut app, auction_id, &new_owner)
+        .unwrap_err();
+    assert_eq!(err, ContractError::OwnershipTransferExpired {});
+
+    contract
+        .propose_new_owner(&mut app, auction_id, &owner, &new_owner, None)
+        .unwrap();
+    contract
+        .cancel_ownership_transfer(&mut app, auction_id, &owner)
+        .unwrap();
+    let err = contract
+        .accept_ownership(&mut app, auction_id, &new_owner)
+        .unwrap_err();
+    assert_eq!(err, ContractError::NoOwnershipTransfer {});
+}
+
+#[test]
+fn renounce_ownership() {
+    let (mut app, contract, auction_id) = init_contract();
+    let owner = Addr::unchecked(OWNER);
+    let err = contract
+        .renounce_ownership(&mut app, auction_id, &owner)
+        .unwrap_err();
+    assert_eq!(err, ContractError::RenounceWithoutEnd {});
+
+    let auction_id = contract
+        .create_auction(
+            &mut app,
+            &owner,
+            CreateAuctionMsg {
+                end: Some(Scheduled::AtHeight(mock_env().block.height + 10)),
+                ..create_auction_msg()
+            },
+        )
+        .unwrap();
+    contract
+        .renounce_ownership(&mut app, auction_id, &owner)
+        .unwrap();
+    let resp = contract.query_ownership(&app, auction_id).unwrap();
+    assert_eq!(resp.owner, None);
+
+    let err = AuctionContract::close(&contract, &mut app, auction_id, &owner).unwrap_err();
+    assert_eq!(err, ContractError::OwnershipRenounced {});
+
+    app.update_block(|block| block.height += 10);
+    AuctionContract::close(&contract, &mut app, auction_id, &owner).unwrap();
+}
+
+#[test]
+fn commission_split_between_fee_recipients() {
+    let (mut app, _, _) = init_contract();
+    let code_id = AuctionContract::store_code(&mut app);
+    let contract = AuctionContract::instantiate(
+        &mut app,
+        code_id,
+        &Addr::unchecked(OWNER),
+        "Marketplace",
+        None,
+        &InstantiateMsg {
+            fee_recipients: Some(vec![
+                FeeRecipient {
+                    address: "platform".to_string(),
+                    weight: 3,
+                },
+                FeeRecipient {
+                    address: "referrer".to_string(),
+                    weight: 1,
+                },
+            ]),
+        },
+    )
+    .unwrap();
+    let auction_id = contract
+        .create_auction(&mut app, &Addr::unchecked(OWNER), create_auction_msg())
+        .unwrap();
+
+    AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_ONE),
+        &coins(40, ATOM),
+    )
+    .unwrap();
+    AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_TWO),
+        &coins(50, ATOM),
+    )
+    .unwrap();
+    AuctionContract::close(&contract, &mut app, auction_id, &Addr::unchecked(OWNER)).unwrap();
+
+    // Commissions of 4 split 3:1, and of 5 split 3:1 with the dust going to the platform
+    assert_eq!(
+        app.wrap().query_all_balances("platform").unwrap(),
+        coins(7, ATOM)
+    );
+    assert_eq!(
+        app.wrap().query_all_balances("referrer").unwrap(),
+        coins(2, ATOM)
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(OWNER).unwrap(),
+        coins(145, ATOM)
+    );
+}
+
+#[test]
+fn fee_recipients_per_auction() {
+    let (mut app, contract, _) = init_contract();
+
+    let err = contract
+        .create_auction(
+            &mut app,
+            &Addr::unchecked(OWNER),
+            CreateAuctionMsg {
+                fee_recipients: Some(vec![FeeRecipient {
+                    address: "platform".to_string(),
+                    weight: 0,
+                }]),
+                ..create_auction_msg()
+            },
+        )
+        .unwrap_err();
+    assert_eq!(err, ContractError::InvalidFeeRecipients {});
+
+    let auction_id = contract
+        .create_auction(
+            &mut app,
+            &Addr::unchecked(OWNER),
+            CreateAuctionMsg {
+                fee_recipients: Some(vec![FeeRecipient {
+                    address: "creator".to_string(),
+                    weight: 1,
+                }]),
+                ..create_auction_msg()
+            },
+        )
+        .unwrap();
+    let resp = AuctionContract::query_info(&contract, &app, auction_id).unwrap();
+    assert_eq!(resp.fee_recipients[0].address, "creator");
+
+    AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_ONE),
+        &coins(40, ATOM),
+    )
+    .unwrap();
+    assert_eq!(
+        app.wrap().query_all_balances("creator").unwrap(),
+        coins(4, ATOM)
+    );
+}
+
+#[test]
+fn commission_on_settlement() {
+    let (mut app, contract, auction_id) = init_contract_with(CreateAuctionMsg {
+        commission_mode: Some(CommissionMode::OnSettlement),
+        ..create_auction_msg()
+    })
+    .unwrap();
+
+    AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_ONE),
+        &coins(20, ATOM),
+    )
+    .unwrap();
+    AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_TWO),
+        &coins(50, ATOM),
+    )
+    .unwrap();
+
+    // Nothing is taken until close
+    let resp =
+        AuctionContract::query_address(&contract, &app, auction_id, &Addr::unchecked(BIDDER_TWO))
+            .unwrap();
+    assert_eq!(resp.bid, Coin::new(50, ATOM));
+    assert_eq!(
+        app.wrap().query_all_balances(OWNER).unwrap(),
+        coins(100, ATOM)
+    );
+
+    AuctionContract::close(&contract, &mut app, auction_id, &Addr::unchecked(OWNER)).unwrap();
+    AuctionContract::retract(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_ONE),
+    )
+    .unwrap();
+
+    assert_eq!(
+        app.wrap().query_all_balances(OWNER).unwrap(),
+        coins(150, ATOM)
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(BIDDER_ONE).unwrap(),
+        coins(100, ATOM)
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(contract.addr()).unwrap(),
+        vec![]
+    );
+}
+
+#[test]
+fn commission_above_full_rejected() {
+    let err = init_contract_with(CreateAuctionMsg {
+        commission: Decimal::percent(150),
+        commission_mode: Some(CommissionMode::OnSettlement),
+        ..create_auction_msg()
+    })
+    .err()
+    .unwrap();
+    assert_eq!(err, ContractError::InvalidCommission {});
+}
+
+#[test]
+fn commission_on_settlement_to_fee_recipient() {
+    let (mut app, contract, auction_id) = init_contract_with(CreateAuctionMsg {
+        commission_mode: Some(CommissionMode::OnSettlement),
+        fee_recipients: Some(vec![FeeRecipient {
+            address: "platform".to_string(),
+            weight: 1,
+        }]),
+        settlement: Some(Settlement::SecondPrice),
+        ..create_auction_msg()
+    })
+    .unwrap();
+
+    AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_ONE),
+        &coins(30, ATOM),
+    )
+    .unwrap();
+    AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_TWO),
+        &coins(50, ATOM),
+    )
+    .unwrap();
+    AuctionContract::close(&contract, &mut app, auction_id, &Addr::unchecked(OWNER)).unwrap();
+
+    // The winner pays 30, of which 3 is commission
+    assert_eq!(
+        app.wrap().query_all_balances("platform").unwrap(),
+        coins(3, ATOM)
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(OWNER).unwrap(),
+        coins(127, ATOM)
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(BIDDER_TWO).unwrap(),
+        coins(70, ATOM)
+    );
+}
+
+#[test]
+fn nft_royalty_paid_to_creator() {
+    let (mut app, contract, _) = init_contract();
+    let nft = init_royalty_nft(&mut app, 10);
+
+    let auction_id = contract
+        .create_auction_with_nft(
+            &mut app,
+            &Addr::unchecked(OWNER),
+            &nft,
+            TOKEN_ID,
+            CreateAuctionMsg {
+                commission_mode: Some(CommissionMode::OnSettlement),
+                ..create_auction_msg()
+            },
+        )
+        .unwrap();
+
+    AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_ONE),
+        &coins(50, ATOM),
+    )
+    .unwrap();
+    AuctionContract::close(&contract, &mut app, auction_id, &Addr::unchecked(OWNER)).unwrap();
+
+    // 50 less 5 commission and 5 royalty
+    assert_eq!(nft_owner(&app, &nft), BIDDER_ONE);
+    assert_eq!(
+        app.wrap().query_all_balances("creator").unwrap(),
+        coins(5, ATOM)
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(OWNER).unwrap(),
+        coins(145, ATOM)
+    );
+
+    let resp = contract.query_settlement(&app, auction_id).unwrap();
+    assert_eq!(
+        resp,
+        SettlementResp {
+            outcome: Outcome::Sold,
+            buyer: Some(Addr::unchecked(BIDDER_ONE)),
+            price: coins(50, ATOM),
+            commission: coins(5, ATOM),
+            royalty_recipient: Some(Addr::unchecked("creator")),
+            royalty: coins(5, ATOM),
+            seller_proceeds: coins(40, ATOM),
+        }
+    );
+}
+
+#[test]
+fn all_bids_paginated() {
+    let (mut app, contract, auction_id) = init_contract();
+
+    AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_ONE),
+        &coins(10, ATOM),
+    )
+    .unwrap();
+    AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_TWO),
+        &coins(20, ATOM),
+    )
+    .unwrap();
+
+    let resp = contract
+        .query_all_bids(&app, auction_id, None, None, None)
+        .unwrap();
+    assert_eq!(
+        resp.bids,
+        vec![
+            BidResp {
+                address: BIDDER_ONE.to_string(),
+                bid: Coin::new(9, ATOM),
+            },
+            BidResp {
+                address: BIDDER_TWO.to_string(),
+                bid: Coin::new(18, ATOM),
+            },
+        ]
+    );
+
+    let resp = contract
+        .query_all_bids(
+            &app,
+            auction_id,
+            Some(&Addr::unchecked(BIDDER_TWO)),
+            Some(1),
+            Some(SortOrder::Descending),
+        )
+        .unwrap();
+    assert_eq!(
+        resp.bids,
+        vec![BidResp {
+            address: BIDDER_ONE.to_string(),
+            bid: Coin::new(9, ATOM),
+        }]
+    );
+}
+
+#[test]
+fn bid_history_records_each_bid() {
+    let (mut app, contract, auction_id) = init_contract();
+
+    AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_ONE),
+        &coins(10, ATOM),
+    )
+    .unwrap();
+    app.update_block(|block| block.height += 1);
+    AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_TWO),
+        &coins(20, ATOM),
+    )
+    .unwrap();
+    AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_ONE),
+        &coins(30, ATOM),
+    )
+    .unwrap();
+
+    let resp = contract
+        .query_bid_history(&app, auction_id, None, None)
+        .unwrap();
+    let bidders: Vec<_> = resp.bids.iter().map(|bid| bid.address.as_str()).collect();
+    assert_eq!(bidders, vec![BIDDER_ONE, BIDDER_TWO, BIDDER_ONE]);
+    assert_eq!(
+        resp.bids[0],
+        BidHistoryEntry {
+            id: 0,
+            address: Addr::unchecked(BIDDER_ONE),
+            gross: Coin::new(10, ATOM),
+            net: Coin::new(9, ATOM),
+            commission: Coin::new(1, ATOM),
+            height: app.block_info().height - 1,
+            time: app.block_info().time,
+        }
+    );
+
+    let resp = contract
+        .query_bid_history(&app, auction_id, Some(1), Some(5))
+        .unwrap();
+    assert_eq!(resp.bids.len(), 1);
+    assert_eq!(resp.bids[0].gross, Coin::new(30, ATOM));
+    assert_eq!(resp.bids[0].net, Coin::new(27, ATOM));
+}
+
+#[test]
+fn top_bids_ordered_by_deposit() {
+    let (mut app, contract, auction_id) = init_contract();
+
+    AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_ONE),
+        &coins(10, ATOM),
+    )
+    .unwrap();
+    AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_TWO),
+        &coins(20, ATOM),
+    )
+    .unwrap();
+    AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_ONE),
+        &coins(20, ATOM),
+    )
+    .unwrap();
+
+    let resp = contract.query_top_bids(&app, auction_id, None).unwrap();
+    assert_eq!(
+        resp.bids,
+        vec![
+            BidResp {
+                address: BIDDER_ONE.to_string(),
+                bid: Coin::new(27, ATOM),
+            },
+            BidResp {
+                address: BIDDER_TWO.to_string(),
+                bid: Coin::new(18, ATOM),
+            },
+        ]
+    );
+
+    let resp = contract.query_top_bids(&app, auction_id, Some(1)).unwrap();
+    assert_eq!(resp.bids.len(), 1);
+    assert_eq!(resp.bids[0].address, BIDDER_ONE);
+}
+
+#[test]
+fn runner_up_replaced_after_withdrawal() {
+    let (mut app, contract, auction_id) = init_contract();
+    let bidder_three = Addr::unchecked("bidder_three");
+    app.send_tokens(
+        Addr::unchecked(OWNER),
+        bidder_three.clone(),
+        &coins(30, ATOM),
+    )
+    .unwrap();
+
+    AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_ONE),
+        &coins(10, ATOM),
+    )
+    .unwrap();
+    AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_TWO),
+        &coins(20, ATOM),
+    )
+    .unwrap();
+    AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &bidder_three,
+        &coins(30, ATOM),
+    )
+    .unwrap();
+
+    AuctionContract::retract(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_TWO),
+    )
+    .unwrap();
+
+    let resp = AuctionContract::query_runner_up(&contract, &app, auction_id).unwrap();
+    assert_eq!(
+        resp,
+        RunnerUpResp {
+            address: BIDDER_ONE.to_string(),
+            bid: Coin::new(9, ATOM),
+        }
+    );
+}
+
+const OSMO: &str = "osmo";
+
+/// Gives both bidders 100 `OSMO` besides their `ATOM`.
+fn init_multi_denom_contract() -> (App, AuctionContract) {
+    let (mut app, contract, _) = init_contract();
+    app.init_modules(|router, _, storage| {
+        for bidder in [BIDDER_ONE, BIDDER_TWO] {
+            router
+                .bank
+                .init_balance(
+                    storage,
+                    &Addr::unchecked(bidder),
+                    vec![Coin::new(100, ATOM), Coin::new(100, OSMO)],
+                )
+                .unwrap();
+        }
+    });
+    (app, contract)
+}
+
+fn multi_denom_auction_msg(rate: Rate) -> CreateAuctionMsg {
+    CreateAuctionMsg {
+        accepted_denoms: Some(vec![AcceptedDenom {
+            denom: OSMO.to_string(),
+            rate,
+        }]),
+        ..create_auction_msg()
+    }
+}
+
+fn oracle_instantiate(
+    _deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _msg: Empty,
+) -> StdResult<Response> {
+    Ok(Response::new())
+}
+
+/// Values every denom at half a unit of the quote denom.
+fn oracle_query(_deps: Deps, _env: Env, msg: OracleQueryMsg) -> StdResult<Binary> {
+    match msg {
+        OracleQueryMsg::ExchangeRate { .. } => to_binary(&ExchangeRateResponse {
+            rate: Decimal::percent(50),
+        }),
+    }
+}
+
+#[test]
+fn bid_in_accepted_denom() {
+    let (mut app, contract) = init_multi_denom_contract();
+    let auction_id = contract
+        .create_auction(
+            &mut app,
+            &Addr::unchecked(OWNER),
+            multi_denom_auction_msg(Rate::Fixed(Decimal::from_ratio(2u128, 1u128))),
+        )
+        .unwrap();
+
+    // 18 osmo after commission are worth 36 atom
+    AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_ONE),
+        &coins(20, OSMO),
+    )
+    .unwrap();
+    let err = AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_TWO),
+        &coins(30, ATOM),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::BidBelowMinimum {
+            min_bid: "37".to_string(),
+        }
+    );
+    AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_TWO),
+        &coins(50, ATOM),
+    )
+    .unwrap();
+    AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_ONE),
+        &coins(10, OSMO),
+    )
+    .unwrap();
+
+    let resp =
+        AuctionContract::query_address(&contract, &app, auction_id, &Addr::unchecked(BIDDER_ONE))
+            .unwrap();
+    assert_eq!(
+        resp,
+        BidsResp {
+            bid: Coin::new(54, ATOM),
+            deposits: coins(27, OSMO),
+        }
+    );
+
+    AuctionContract::close(&contract, &mut app, auction_id, &Addr::unchecked(OWNER)).unwrap();
+    AuctionContract::retract(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_TWO),
+    )
+    .unwrap();
+
+    // The winner pays in the osmo deposited, commission stays in the denom it was taken in
+    assert_eq!(
+        app.wrap().query_all_balances(OWNER).unwrap(),
+        vec![Coin::new(105, ATOM), Coin::new(30, OSMO)]
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(BIDDER_TWO).unwrap(),
+        vec![Coin::new(95, ATOM), Coin::new(100, OSMO)]
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(contract.addr()).unwrap(),
+        vec![]
+    );
+    let resp = contract.query_settlement(&app, auction_id).unwrap();
+    assert_eq!(resp.price, coins(27, OSMO));
+}
+
+#[test]
+fn bid_at_oracle_rate() {
+    let (mut app, contract) = init_multi_denom_contract();
+    let code_id = app.store_code(Box::new(ContractWrapper::new(
+        oracle_instantiate,
+        oracle_instantiate,
+        oracle_query,
+    )));
+    let oracle = app
+        .instantiate_contract(
+            code_id,
+            Addr::unchecked(OWNER),
+            &Empty {},
+            &[],
+            "Oracle",
+            None,
+        )
+        .unwrap();
+    let auction_id = contract
+        .create_auction(
+            &mut app,
+            &Addr::unchecked(OWNER),
+            multi_denom_auction_msg(Rate::Oracle {
+                contract: oracle.to_string(),
+            }),
+        )
+        .unwrap();
+
+    AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_ONE),
+        &coins(40, OSMO),
+    )
+    .unwrap();
+
+    let resp = contract.query_top_bids(&app, auction_id, None).unwrap();
+    assert_eq!(
+        resp.bids,
+        vec![BidResp {
+            address: BIDDER_ONE.to_string(),
+            bid: Coin::new(18, ATOM),
+        }]
+    );
+}
+
+#[test]
+fn accepted_denoms_validated() {
+    let (mut app, contract) = init_multi_denom_contract();
+
+    let err = contract
+        .create_auction(
+            &mut app,
+            &Addr::unchecked(OWNER),
+            CreateAuctionMsg {
+                accepted_denoms: Some(vec![AcceptedDenom {
+                    denom: ATOM.to_string(),
+                    rate: Rate::Fixed(Decimal::one()),
+                }]),
+                ..create_auction_msg()
+            },
+        )
+        .unwrap_err();
+    assert_eq!(err, ContractError::InvalidAcceptedDenoms {});
+
+    let err = contract
+        .create_auction(
+            &mut app,
+            &Addr::unchecked(OWNER),
+            multi_denom_auction_msg(Rate::Fixed(Decimal::zero())),
+        )
+        .unwrap_err();
+    assert_eq!(err, ContractError::InvalidAcceptedDenoms {});
+
+    let err = contract
+        .create_auction(
+            &mut app,
+            &Addr::unchecked(OWNER),
+            CreateAuctionMsg {
+                settlement: Some(Settlement::SecondPrice),
+                ..multi_denom_auction_msg(Rate::Fixed(Decimal::one()))
+            },
+        )
+        .unwrap_err();
+    assert_eq!(err, ContractError::WrongAuctionMode {});
+}
+
+#[test]
+fn stray_funds_rejected() {
+    let (mut app, contract) = init_multi_denom_contract();
+    let auction_id = contract
+        .create_auction(&mut app, &Addr::unchecked(OWNER), create_auction_msg())
+        .unwrap();
+
+    let err = AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_ONE),
+        &[Coin::new(20, ATOM), Coin::new(5, OSMO)],
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::StrayFunds {
+            denom: OSMO.to_string(),
+        }
+    );
+
+    AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_ONE),
+        &coins(20, ATOM),
+    )
+    .unwrap();
+    let err = app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            contract.addr().clone(),
+            &ExecMsg::Close {
+                auction_id,
+                reserve: None,
+            },
+            &coins(5, ATOM),
+        )
+        .unwrap_err()
+        .downcast::<ContractError>()
+        .unwrap();
+    assert_eq!(
+        err,
+        ContractError::StrayFunds {
+            denom: ATOM.to_string(),
+        }
+    );
+
+    let err = app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            contract.addr().clone(),
+            &ExecMsg::CreateAuction(create_auction_msg()),
+            &coins(5, ATOM),
+        )
+        .unwrap_err()
+        .downcast::<ContractError>()
+        .unwrap();
+    assert_eq!(
+        err,
+        ContractError::StrayFunds {
+            denom: ATOM.to_string(),
+        }
+    );
+
+    let err = app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            contract.addr().clone(),
+            &ExecMsg::ProposeNewOwner {
+                auction_id,
+                new_owner: BIDDER_TWO.to_string(),
+                expiry: None,
+            },
+            &coins(5, ATOM),
+        )
+        .unwrap_err()
+        .downcast::<ContractError>()
+        .unwrap();
+    assert_eq!(
+        err,
+        ContractError::StrayFunds {
+            denom: ATOM.to_string(),
+        }
+    );
+}
+
+#[test]
+fn stray_funds_refunded() {
+    let (mut app, contract) = init_multi_denom_contract();
+    let auction_id = contract
+        .create_auction(
+            &mut app,
+            &Addr::unchecked(OWNER),
+            CreateAuctionMsg {
+                stray_funds: Some(StrayFunds::Refund),
+                ..create_auction_msg()
+            },
+        )
+        .unwrap();
+
+    AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_ONE),
+        &[Coin::new(20, ATOM), Coin::new(5, OSMO)],
+    )
+    .unwrap();
+    assert_eq!(
+        app.wrap().query_all_balances(BIDDER_ONE).unwrap(),
+        vec![Coin::new(80, ATOM), Coin::new(100, OSMO)]
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(contract.addr()).unwrap(),
+        coins(18, ATOM)
+    );
+
+    let resp = app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            contract.addr().clone(),
+            &ExecMsg::Close {
+                auction_id,
+                reserve: None,
+            },
+            &coins(5, ATOM),
+        )
+        .unwrap();
+    let refunded = resp
+        .custom_attrs(1)
+        .iter()
+        .find(|attr| attr.key == "stray_refunded")
+        .unwrap();
+    assert_eq!(refunded.value, "5atom");
+    assert_eq!(
+        app.wrap().query_all_balances(contract.addr()).unwrap(),
+        vec![]
+    );
+
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        contract.addr().clone(),
+        &ExecMsg::CreateAuction(CreateAuctionMsg {
+            stray_funds: Some(StrayFunds::Refund),
+            ..create_auction_msg()
+        }),
+        &coins(5, ATOM),
+    )
+    .unwrap();
+    assert_eq!(
+        app.wrap().query_all_balances(contract.addr()).unwrap(),
+        vec![]
+    );
+}
+
+#[test]
+fn recover_stray_funds() {
+    let (mut app, contract, auction_id) = init_contract();
+
+    AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_ONE),
+        &coins(20, ATOM),
+    )
+    .unwrap();
+    app.send_tokens(
+        Addr::unchecked(BIDDER_TWO),
+        contract.addr().clone(),
+        &coins(15, ATOM),
+    )
+    .unwrap();
+
+    let err = contract
+        .recover_stray_funds(&mut app, &Addr::unchecked(BIDDER_TWO), ATOM)
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Unauthorized {
+            owner: OWNER.to_string(),
+        }
+    );
+
+    // Only the tokens sent outside a bid are recovered, the deposit stays
+    contract
+        .recover_stray_funds(&mut app, &Addr::unchecked(OWNER), ATOM)
+        .unwrap();
+    assert_eq!(
+        app.wrap().query_all_balances(contract.addr()).unwrap(),
+        coins(18, ATOM)
+    );
+    assert_eq!(
+        app.wrap().query_all_balances(OWNER).unwrap(),
+        coins(117, ATOM)
+    );
+
+    let err = contract
+        .recover_stray_funds(&mut app, &Addr::unchecked(OWNER), ATOM)
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::NoStrayFunds {
+            denom: ATOM.to_string(),
+        }
+    );
+
+    // The winner's deposit has been paid out on close
+    AuctionContract::close(&contract, &mut app, auction_id, &Addr::unchecked(OWNER)).unwrap();
+    let err = contract
+        .recover_stray_funds(&mut app, &Addr::unchecked(OWNER), ATOM)
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::NoStrayFunds {
+            denom: ATOM.to_string(),
+        }
+    );
+}
+
+#[test]
+fn solvency_reports_surplus() {
+    let (mut app, contract, auction_id) = init_contract();
+
+    AuctionContract::bid(
+        &contract,
+        &mut app,
+        auction_id,
+        &Addr::unchecked(BIDDER_ONE),
+        &coins(20, ATOM),
+    )
+    .unwrap();
+    app.send_tokens(
+        Addr::unchecked(BIDDER_TWO),
+        contract.addr().clone(),
+        &coins(15, ATOM),
+    )
+    .unwrap();
+
+    assert_eq!(
+        contract.query_solvency(&app).unwrap(),
+        SolvencyResp {
+            solvent: true,
+            denoms: vec![DenomSolvency {
+                denom: ATOM.to_string(),
+                balance: Uint128::new(33),
+                obligations: Uint128::new(18),
+                surplus: Uint128::new(15),
+                deficit: Uint128::zero(),
+            }],
+        }
+    );
+
+    // The winner's deposit leaves the contract with the payout
+    AuctionContract::close(&contract, &mut app, auction_id, &Addr::unchecked(OWNER)).unwrap();
+    assert_eq!(
+        contract.query_solvency(&app).unwrap(),
+        SolvencyResp {
+            solvent: true,
+            denoms: vec![DenomSolvency {
+                denom: ATOM.to_string(),
+                balance: Uint128::new(15),
+                obligations: Uint128::zero(),
+                surplus: Uint128::new(15),
+                deficit: Uint128::zero(),
+            }],
+        }
+    );
+}
@@ -1,14 +1,107 @@
-use cosmwasm_std::{Addr, Coin, Decimal};
-use cw_storage_plus::{Item, Map};
+use cosmwasm_std::{Addr, BlockInfo, Coin, Decimal, HexBinary, Uint128};
+use cw_storage_plus::{Index, IndexList, IndexedMap, Item, KeyDeserialize, Map, MultiIndex};
+use cw_utils::Scheduled;
 use serde::{Deserialize, Serialize};
 
+use crate::msg::{
+    AntiSniping, AssetKind, AuctionMode, BidHistoryEntry, CommissionMode, Lot, MinIncrement,
+    Outcome, Reserve, Settlement, StrayFunds,
+};
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct BaseInfo {
     pub owner: Addr,
     pub commodity: String,
+    /// Escrowed asset, `None` when the commodity is delivered off-chain.
+    pub lot: Option<Lot>,
     pub bidding_denom: String,
+    pub bidding_asset: AssetKind,
     pub commission: Decimal,
+    pub start: Option<Scheduled>,
+    pub end: Option<Scheduled>,
+    pub anti_sniping: Option<AntiSniping>,
+    /// The end after all anti-sniping extensions were applied.
+    pub effective_end: Option<Scheduled>,
+    pub reserve: Option<Reserve>,
+    pub starting_price: Uint128,
+    pub min_increment: Option<MinIncrement>,
+    pub mode: AuctionMode,
+    pub settlement: Settlement,
+    pub buy_now_price: Option<Uint128>,
+    pub refund_on_outbid: bool,
+    pub withdrawal_penalty: Decimal,
+    /// Commission split, the owner gets it all if empty.
+    pub fee_recipients: Vec<FeeShare>,
+    pub commission_mode: CommissionMode,
+    /// Denoms accepted besides the bidding denom, deposits are then tracked in `DEPOSITS`.
+    pub accepted_denoms: Vec<DenomRate>,
+    pub stray_funds: StrayFunds,
+    /// Nobody controls the auction anymore, `owner` is still paid.
+    pub renounced: bool,
     pub active: bool,
+    pub outcome: Outcome,
+}
+
+impl BaseInfo {
+    pub fn has_ended(&self, block: &BlockInfo) -> bool {
+        self.effective_end
+            .is_some_and(|end| end.is_triggered(block))
+    }
+
+    pub fn accepts(&self, denom: &str) -> bool {
+        denom == self.bidding_denom
+            || self
+                .accepted_denoms
+                .iter()
+                .any(|accepted| accepted.denom == denom)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct DenomRate {
+    pub denom: String,
+    pub rate: RateSource,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum RateSource {
+    Fixed(Decimal),
+    Oracle(Addr),
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+pub struct Config {
+    pub fee_recipients: Vec<FeeShare>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct FeeShare {
+    pub address: Addr,
+    pub weight: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PendingOwner {
+    pub address: Addr,
+    pub expiry: Option<Scheduled>,
+}
+
+/// Sealed bid commitment, the deposit itself is kept in `BIDS` until reveal.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SealedBid {
+    pub commitment: HexBinary,
+    /// Bid after commission, once revealed.
+    pub revealed: Option<Uint128>,
+    /// Order of the latest commitment in the auction, taken from `BID_SEQ`.
+    pub seq: u64,
+}
+
+/// Order in a multi-unit auction, the deposit is kept in `BIDS`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct UnitBid {
+    pub bidder: Addr,
+    pub quantity: u64,
+    pub price: Uint128,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
@@ -17,6 +110,95 @@ pub struct Bid {
     pub bid: Coin,
 }
 
-pub const BASE_INFO: Item<BaseInfo> = Item::new("base_info");
-pub const BIDS: Map<Addr, Coin> = Map::new("bids");
-pub const HIGHEST_BID: Item<Bid> = Item::new("highest_bid");
+/// Payouts of a sold single-lot auction, per denom paid.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Sale {
+    pub buyer: Addr,
+    pub price: Vec<Coin>,
+    pub commission: Vec<Coin>,
+    pub royalties: Vec<Royalty>,
+    pub seller_proceeds: Vec<Coin>,
+}
+
+/// Creator royalty reported by the CW2981 contract of an NFT lot.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Royalty {
+    pub recipient: Addr,
+    pub amount: Coin,
+}
+
+pub const CONFIG: Item<Config> = Item::new("config");
+pub const NEXT_AUCTION_ID: Item<u64> = Item::new("next_auction_id");
+pub const AUCTIONS: Map<u64, BaseInfo> = Map::new("auctions");
+pub struct BidIndexes<'a> {
+    /// `(auction_id, amount)`, for ranking the deposits of an auction.
+    pub amount: MultiIndex<'a, (u64, u128), Coin, (u64, Addr)>,
+}
+
+impl<'a> IndexList<Coin> for BidIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Coin>> + '_> {
+        let v: Vec<&dyn Index<Coin>> = vec![&self.amount];
+        Box::new(v.into_iter())
+    }
+}
+
+/// Deposits held for bidders, keyed by `(auction_id, bidder)`.
+pub fn bids<'a>() -> IndexedMap<'a, (u64, Addr), Coin, BidIndexes<'a>> {
+    let indexes = BidIndexes {
+        amount: MultiIndex::new(
+            |pk, bid| {
+                // Primary keys are always written by the map itself
+                let (auction_id, _) = <(u64, Addr)>::from_slice(pk).expect("invalid bid key");
+                (auction_id, bid.amount.u128())
+            },
+            "auction_bids",
+            "auction_bids__amount",
+        ),
+    };
+    IndexedMap::new("auction_bids", indexes)
+}
+
+/// Coins held for bidders keyed by `(auction_id, bidder, denom)`, in auctions accepting
+/// several denoms. `bids()` then holds their value in the bidding denom.
+pub const DEPOSITS: Map<(u64, Addr, String), Uint128> = Map::new("auction_deposits");
+
+/// Native funds deposited by bidders of all auctions and not paid out yet, by denom.
+pub const HELD: Map<&str, Uint128> = Map::new("held_funds");
+pub const HIGHEST_BID: Map<u64, Bid> = Map::new("auction_highest_bid");
+/// Best bidder other than the leader, `bid` being their total after commission.
+pub const RUNNER_UP: Map<u64, Bid> = Map::new("auction_runner_up");
+pub const PENDING_OWNER: Map<u64, PendingOwner> = Map::new("auction_pending_owner");
+pub const SEALED_BIDS: Map<(u64, Addr), SealedBid> = Map::new("auction_sealed_bids");
+/// Multi-unit orders keyed by `(auction_id, price, u64::MAX - seq)`, so a descending range
+/// goes from the best price and the earliest order within a price.
+pub const BID_BOOK: Map<(u64, u128, u64), UnitBid> = Map::new("auction_bid_book");
+/// Next sequence number of multi-unit orders and sealed commitments, per auction.
+pub const BID_SEQ: Map<u64, u64> = Map::new("auction_bid_seq");
+pub const ALLOCATIONS: Map<(u64, Addr), u64> = Map::new("auction_allocations");
+pub const CLEARING_PRICE: Map<u64, Uint128> = Map::new("auction_clearing_price");
+pub const SALES: Map<u64, Sale> = Map::new("auction_sales");
+/// Append-only record of every bid, keyed by `(auction_id, id)`.
+pub const BID_HISTORY: Map<(u64, u64), BidHistoryEntry> = Map::new("auction_bid_history");
+pub const BID_HISTORY_LEN: Map<u64, u64> = Map::new("auction_bid_history_len");
+
+/// Storage of the 1.x contract, which hosted a single auction.
+pub mod v1 {
+    use cosmwasm_std::{Addr, Coin, Decimal};
+    use cw_storage_plus::{Item, Map};
+    use serde::{Deserialize, Serialize};
+
+    use super::Bid;
+
+    #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+    pub struct BaseInfo {
+        pub owner: Addr,
+        pub commodity: String,
+        pub bidding_denom: String,
+        pub commission: Decimal,
+        pub active: bool,
+    }
+
+    pub const BASE_INFO: Item<BaseInfo> = Item::new("base_info");
+    pub const BIDS: Map<Addr, Coin> = Map::new("bids");
+    pub const HIGHEST_BID: Item<Bid> = Item::new("highest_bid");
+}